@@ -0,0 +1,55 @@
+use std::ffi::OsStr;
+
+use crate::com::{check, ComPtr};
+use crate::{ffi, wide, Result};
+
+/// Activates the packaged app with the AppUserModelID `aumid` for the file at `path`,
+/// using `IApplicationActivationManager::ActivateForFile`.
+///
+/// # Returns
+/// The identifier of the activated process.
+pub(crate) fn activate_for_file(aumid: &OsStr, path: &OsStr) -> Result<u32> {
+    let aumid = wide(aumid);
+    let path = wide(path);
+    let verb = wide("open");
+    unsafe {
+        ffi::CoInitialize(std::ptr::null());
+
+        let mut item = std::ptr::null_mut();
+        check(ffi::SHCreateItemFromParsingName(
+            path.as_ptr(),
+            std::ptr::null_mut(),
+            &ffi::IID_ISHELL_ITEM,
+            &mut item,
+        ))?;
+        let item = ComPtr::<ffi::IUnknownVtbl>(item.cast());
+
+        let mut items = std::ptr::null_mut();
+        check(ffi::SHCreateShellItemArrayFromShellItem(
+            item.this(),
+            &ffi::IID_ISHELL_ITEM_ARRAY,
+            &mut items,
+        ))?;
+        let items = ComPtr::<ffi::IUnknownVtbl>(items.cast());
+
+        let mut manager = std::ptr::null_mut();
+        check(ffi::CoCreateInstance(
+            &ffi::CLSID_APPLICATION_ACTIVATION_MANAGER,
+            std::ptr::null_mut(),
+            ffi::CLSCTX_LOCAL_SERVER,
+            &ffi::IID_IAPPLICATION_ACTIVATION_MANAGER,
+            &mut manager,
+        ))?;
+        let manager = ComPtr::<ffi::IApplicationActivationManagerVtbl>(manager.cast());
+
+        let mut process_id = 0;
+        check((manager.vtable().ActivateForFile)(
+            manager.this(),
+            aumid.as_ptr(),
+            items.this(),
+            verb.as_ptr(),
+            &mut process_id,
+        ))?;
+        Ok(process_id)
+    }
+}
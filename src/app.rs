@@ -0,0 +1,121 @@
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::{CommandRunExt, Error, ErrorKind, Result};
+
+/// The executable extensions used when `PATHEXT` isn't set.
+const DEFAULT_PATHEXT: &str = ".COM;.EXE;.BAT;.CMD";
+
+/// The registry keys of the apps registered in `App Paths`, for the current user and the machine.
+const APP_PATHS_KEYS: [&str; 2] = [
+    r"HKCU\Software\Microsoft\Windows\CurrentVersion\App Paths",
+    r"HKLM\Software\Microsoft\Windows\CurrentVersion\App Paths",
+];
+
+/// Searches for the executable `app` the way the shell would.
+///
+/// Paths containing a directory are checked directly, bare names are searched in
+/// the current directory and then in every directory of `PATH`. If `app` has no
+/// extension, each extension listed in `PATHEXT` is tried.
+///
+/// # Returns
+/// The path of the executable if found, `None` otherwise.
+pub(crate) fn find_executable(app: &OsStr) -> Option<PathBuf> {
+    let app = Path::new(app);
+    if app.parent().is_some_and(|dir| !dir.as_os_str().is_empty()) {
+        return with_extensions(app).find(|path| path.is_file());
+    }
+
+    let mut dirs = vec![env::current_dir().ok()?];
+    if let Some(path) = env::var_os("PATH") {
+        dirs.extend(env::split_paths(&path));
+    }
+
+    dirs.iter()
+        .flat_map(|dir| with_extensions(&dir.join(app)).collect::<Vec<_>>())
+        .find(|path| path.is_file())
+}
+
+/// Returns `true` if `app` is found by [`find_executable()`], or is registered in `App Paths`.
+///
+/// `App Paths` lets the shell find apps that aren't in `PATH`, such as `firefox`.
+/// Only bare names are looked up there, with `.exe` appended if they have no extension.
+pub(crate) fn is_available(app: &OsStr) -> bool {
+    if find_executable(app).is_some() {
+        return true;
+    }
+
+    if Path::new(app).components().count() != 1 {
+        return false;
+    }
+    let mut name = app.to_os_string();
+    if Path::new(app).extension().is_none() {
+        name.push(".exe");
+    }
+
+    APP_PATHS_KEYS.iter().any(|key| {
+        let mut key = OsString::from(key);
+        key.push("\\");
+        key.push(&name);
+
+        let mut cmd = Command::new("reg.exe");
+        cmd.arg("query").arg(key).arg("/ve");
+        cmd.query_output()
+            .is_ok_and(|output| output.status.success())
+    })
+}
+
+/// Resolves `app` relative to the directory of the current executable.
+///
+/// # Returns
+/// The path of the bundled app, or an error of kind `NOT_FOUND` if it doesn't exist.
+pub(crate) fn resolve_bundled(app: &Path) -> Result<PathBuf> {
+    let exe = env::current_exe()?;
+    let dir = exe.parent().unwrap_or(Path::new(""));
+    let resolved = dir.join(app);
+    if resolved.is_file() {
+        Ok(resolved)
+    } else {
+        Err(Error::new(ErrorKind::NOT_FOUND, resolved.to_string_lossy()))
+    }
+}
+
+/// Returns `true` if `app` has no extension, or one listed in `PATHEXT` (such as `.exe` or `.bat`).
+pub(crate) fn has_executable_extension(app: &OsStr) -> bool {
+    match Path::new(app).extension() {
+        Some(extension) => {
+            let extension = format!(".{}", extension.to_string_lossy());
+            path_extensions()
+                .iter()
+                .any(|ext| ext.eq_ignore_ascii_case(&extension))
+        }
+        None => true,
+    }
+}
+
+/// The executable extensions listed in `PATHEXT`, such as `.EXE`.
+fn path_extensions() -> Vec<String> {
+    env::var("PATHEXT")
+        .unwrap_or_else(|_| DEFAULT_PATHEXT.to_string())
+        .split(';')
+        .filter(|ext| !ext.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Yields `path` itself if it has an extension, or `path` with each `PATHEXT` extension appended.
+fn with_extensions(path: &Path) -> impl Iterator<Item = PathBuf> + '_ {
+    let extensions = if path.extension().is_some() {
+        vec![OsString::new()]
+    } else {
+        path_extensions().into_iter().map(OsString::from).collect()
+    };
+
+    extensions.into_iter().map(move |ext| {
+        let mut path = path.as_os_str().to_os_string();
+        path.push(ext);
+        PathBuf::from(path)
+    })
+}
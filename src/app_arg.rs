@@ -0,0 +1,48 @@
+use std::ffi::OsString;
+
+/// An app to open paths with, accepted by [`with()`](crate::with) and related functions.
+///
+/// This is implemented for `&str`, `String`, `&OsStr`, `OsString`, `&Path` and `PathBuf`,
+/// so apps given as a path are passed on as-is rather than converted to UTF-8 lossily.
+/// It is sealed, so it can't be implemented outside of this crate.
+///
+/// # Examples
+///
+/// ```
+/// use std::ffi::{OsStr, OsString};
+/// use std::path::{Path, PathBuf};
+/// use win_open::WindowsShell;
+///
+/// let app = r"C:\Program Files\App\app.exe";
+/// let commands = [
+///     win_open::with_command_with_shell("notes.txt", app, WindowsShell::Cmd),
+///     win_open::with_command_with_shell("notes.txt", app.to_string(), WindowsShell::Cmd),
+///     win_open::with_command_with_shell("notes.txt", OsStr::new(app), WindowsShell::Cmd),
+///     win_open::with_command_with_shell("notes.txt", OsString::from(app), WindowsShell::Cmd),
+///     win_open::with_command_with_shell("notes.txt", Path::new(app), WindowsShell::Cmd),
+///     win_open::with_command_with_shell("notes.txt", PathBuf::from(app), WindowsShell::Cmd),
+/// ];
+///
+/// let quoted = format!("\"{app}\"");
+/// for cmd in commands {
+///     assert_eq!(cmd.get_args().nth(3).unwrap(), quoted.as_str());
+/// }
+/// ```
+pub trait AppArg: private::Sealed {}
+
+pub(crate) mod private {
+    use std::ffi::OsString;
+
+    pub trait Sealed {
+        /// Converts the app to the string passed to the launcher.
+        fn into_app(self) -> OsString;
+    }
+}
+
+impl<T: Into<OsString>> AppArg for T {}
+
+impl<T: Into<OsString>> private::Sealed for T {
+    fn into_app(self) -> OsString {
+        self.into()
+    }
+}
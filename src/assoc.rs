@@ -0,0 +1,59 @@
+use std::ffi::{OsStr, OsString};
+
+use crate::com::check;
+use crate::{ffi, from_wide, wide, Error, ErrorKind, Result};
+
+/// `ASSOCSTR_FRIENDLYDOCNAME`: the friendly name of a document type, such as "PDF Document".
+pub(crate) const ASSOCSTR_FRIENDLYDOCNAME: u32 = 3;
+
+/// `HRESULT_FROM_WIN32(ERROR_NO_ASSOCIATION)`, returned for unregistered extensions.
+const E_NO_ASSOCIATION: i32 = 0x8007_0483_u32 as i32;
+
+/// Queries the `ASSOCSTR_*` string `kind` of the association `assoc`, such as an extension
+/// (`.txt`) or a ProgID, using `AssocQueryStringW`.
+pub(crate) fn query_string(assoc: &OsStr, kind: u32) -> Result<OsString> {
+    let assoc_wide = wide(assoc);
+    let no_association = || {
+        Error::new(
+            ErrorKind::NO_ASSOCIATION,
+            format!("no association for '{}'", assoc.to_string_lossy()),
+        )
+    };
+
+    unsafe {
+        // The first call only retrieves the required length, including the null character.
+        let mut len = 0u32;
+        let hresult = ffi::AssocQueryStringW(
+            0,
+            kind,
+            assoc_wide.as_ptr(),
+            std::ptr::null(),
+            std::ptr::null_mut(),
+            &mut len,
+        );
+        if hresult == E_NO_ASSOCIATION {
+            return Err(no_association());
+        }
+        check(hresult)?;
+
+        let mut buffer = vec![0u16; len as usize];
+        let hresult = ffi::AssocQueryStringW(
+            0,
+            kind,
+            assoc_wide.as_ptr(),
+            std::ptr::null(),
+            buffer.as_mut_ptr(),
+            &mut len,
+        );
+        if hresult == E_NO_ASSOCIATION {
+            return Err(no_association());
+        }
+        check(hresult)?;
+
+        let value = from_wide(&buffer);
+        if value.is_empty() {
+            return Err(no_association());
+        }
+        Ok(value)
+    }
+}
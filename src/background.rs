@@ -0,0 +1,150 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+
+use crate::{Error, ErrorKind, Result};
+
+/// The worker hasn't started the open yet.
+const PENDING: u8 = 0;
+/// The worker started the open, which can no longer be cancelled.
+const STARTED: u8 = 1;
+/// The open was cancelled before the worker started it.
+const CANCELLED: u8 = 2;
+
+/// A handle to an open running in a background thread, which can be cancelled.
+///
+/// Created by [`that_in_background_cancellable()`](crate::that_in_background_cancellable).
+/// Cancelling only prevents the open from starting; a launcher that is already running
+/// can't be stopped. As the worker starts right away, cancelling usually comes too late:
+/// [`cancel()`](Self::cancel) tells whether it prevented the open. If it did, the handle
+/// reports [`ErrorKind::CANCELLED`] right away, otherwise the actual result of the open.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use win_open::ErrorKind;
+///
+/// let open = win_open::that_in_background_cancellable("http://rust-lang.org");
+/// if open.cancel() {
+///     let err = open.wait_timeout(Duration::from_secs(1)).unwrap().unwrap_err();
+///     assert_eq!(err.kind(), &ErrorKind::CANCELLED);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct BackgroundOpen {
+    state: Arc<AtomicU8>, // Whether the open is pending, started or cancelled
+    result: mpsc::Receiver<Result<()>>, // The result sent by the worker once it's done
+}
+
+impl BackgroundOpen {
+    /// Runs `open` in a new thread, unless cancelled before it starts.
+    pub(crate) fn spawn(open: impl FnOnce() -> Result<()> + Send + 'static) -> Self {
+        let state = Arc::new(AtomicU8::new(PENDING));
+        let (sender, result) = mpsc::channel();
+
+        let worker_state = Arc::clone(&state);
+        std::thread::spawn(move || {
+            let started = worker_state
+                .compare_exchange(PENDING, STARTED, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok();
+            let result = if started {
+                open()
+            } else {
+                Err(cancelled_error())
+            };
+            // The handle may have been dropped, in which case nobody is interested in the result.
+            let _ = sender.send(result);
+        });
+
+        Self { state, result }
+    }
+
+    /// Cancels the open, if it hasn't started yet.
+    ///
+    /// # Returns
+    /// `true` if the open was prevented, `false` if it had already started.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::{Arc, Barrier};
+    /// use win_open::{Error, ErrorKind, WindowsShell};
+    ///
+    /// // Run the launchers in a shell and veto them, so nothing is opened for real.
+    /// win_open::set_shell(Some(WindowsShell::Cmd));
+    /// let barrier = Arc::new(Barrier::new(2));
+    /// let gate = Arc::clone(&barrier);
+    /// let first = AtomicBool::new(true);
+    /// win_open::set_pre_open_hook(move |_| {
+    ///     // Hold the first launcher until the open was cancelled.
+    ///     if first.swap(false, Ordering::SeqCst) {
+    ///         gate.wait();
+    ///         gate.wait();
+    ///     }
+    ///     Err(Error::new(ErrorKind::COMMAND_FAILED, "vetoed"))
+    /// });
+    ///
+    /// // Once the open started, cancelling doesn't prevent it, and its result is reported.
+    /// let open = win_open::that_in_background_cancellable("https://example.com");
+    /// barrier.wait();
+    /// assert!(!open.cancel());
+    /// barrier.wait();
+    /// assert!(!open.is_cancelled());
+    /// assert_eq!(open.wait().unwrap_err().kind(), &ErrorKind::COMMAND_FAILED);
+    ///
+    /// // Whether cancelling right away prevents the open depends on the worker thread.
+    /// let open = win_open::that_in_background_cancellable("https://example.com");
+    /// let prevented = open.cancel();
+    /// assert_eq!(open.is_cancelled(), prevented);
+    /// assert_eq!(open.wait().unwrap_err().kind() == &ErrorKind::CANCELLED, prevented);
+    ///
+    /// win_open::clear_pre_open_hook();
+    /// win_open::set_shell(None);
+    /// ```
+    pub fn cancel(&self) -> bool {
+        self.state
+            .compare_exchange(PENDING, CANCELLED, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+    }
+
+    /// Returns `true` if the open was prevented by [`cancel()`](Self::cancel).
+    pub fn is_cancelled(&self) -> bool {
+        self.state.load(Ordering::SeqCst) == CANCELLED
+    }
+
+    /// Waits for the open to finish, for at most `timeout`.
+    ///
+    /// # Parameters
+    /// - `timeout`: The maximum duration to wait.
+    ///
+    /// # Returns
+    /// - `Some(Err(_))` of kind [`ErrorKind::CANCELLED`] if the open was prevented.
+    /// - `Some(result)` with the result of the open, if it finished in time.
+    ///   The result is only returned once; later calls return `None`.
+    /// - `None` if the open didn't finish in time.
+    pub fn wait_timeout(&self, timeout: Duration) -> Option<Result<()>> {
+        if self.is_cancelled() {
+            return Some(Err(cancelled_error()));
+        }
+        self.result.recv_timeout(timeout).ok()
+    }
+
+    /// Blocks until the open finishes.
+    ///
+    /// # Returns
+    /// The result of the open, or an error of kind [`ErrorKind::CANCELLED`] if it was prevented.
+    pub fn wait(self) -> Result<()> {
+        if self.is_cancelled() {
+            return Err(cancelled_error());
+        }
+        self.result
+            .recv()
+            .unwrap_or_else(|_| Err(Error::new(ErrorKind::NO_LAUNCHER, "")))
+    }
+}
+
+fn cancelled_error() -> Error {
+    Error::new(ErrorKind::CANCELLED, "background open was cancelled")
+}
@@ -0,0 +1,324 @@
+use std::ffi::{OsStr, OsString};
+use std::os::windows::process::CommandExt;
+use std::process::Command;
+
+use crate::escape::{nu_string_literal, powershell_string_literal, wrap_in_quotes};
+use crate::{
+    target, CommandRunExt, IntoResult, OpenOptions, Result, WindowsShell, CREATE_NO_WINDOW,
+};
+
+/// The maximum length of a script passed to a single shell, below `cmd`'s limit of 8191 characters.
+const MAX_SCRIPT_LEN: usize = 8000;
+
+/// A batch of targets, opened with as few shell invocations as possible.
+///
+/// Opening many targets one by one with [`that()`](crate::that) spawns a shell for each of them.
+/// An `OpenBatch` instead accumulates targets and opens them with a single shell running
+/// all of them in turn, e.g. `cmd /c start "" "a" & start "" "b"`. Very large batches are split
+/// to stay within the command line limits of the shell.
+///
+/// With the `shellexecute-open` feature, unless a shell is chosen explicitly, each target is
+/// opened with `ShellExecuteExW` instead, which doesn't spawn any shell.
+///
+/// The batch is opened when [`flush()`](Self::flush) is called, or when it's dropped.
+/// Errors on drop are ignored, so call `flush()` to handle them.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut batch = win_open::OpenBatch::new();
+/// for i in 0..50 {
+///     batch.add(format!("photo-{i}.jpg"));
+/// }
+/// batch.flush()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct OpenBatch {
+    targets: Vec<OsString>, // The targets added since the last flush
+    options: OpenOptions,   // The options used to build the commands
+}
+
+impl OpenBatch {
+    /// Creates an empty batch, using the default options.
+    ///
+    /// # Returns
+    /// A new `OpenBatch` instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty batch, using `options` to pick the shell and build its commands.
+    ///
+    /// # Parameters
+    /// - `options`: The options to use.
+    ///
+    /// # Returns
+    /// A new `OpenBatch` instance.
+    pub fn with_options(options: OpenOptions) -> Self {
+        Self {
+            targets: Vec::new(),
+            options,
+        }
+    }
+
+    /// Adds a target to the batch, to be opened with the default application on the next flush.
+    ///
+    /// # Parameters
+    /// - `target`: The path or URL to open.
+    pub fn add(&mut self, target: impl AsRef<OsStr>) -> &mut Self {
+        self.targets.push(target.as_ref().to_os_string());
+        self
+    }
+
+    /// Returns the number of targets waiting to be opened.
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    /// Returns `true` if no targets are waiting to be opened.
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// Removes all targets from the batch without opening them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut batch = win_open::OpenBatch::new();
+    /// batch.add("notes.txt");
+    /// batch.clear();
+    /// assert!(batch.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.targets.clear();
+    }
+
+    /// Get the commands that [`flush()`](Self::flush) would run for the targets in the batch.
+    ///
+    /// Control Panel items and app URIs are opened by a command of their own,
+    /// like [`commands()`](crate::commands).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use win_open::{OpenBatch, OpenOptions, WindowsShell};
+    ///
+    /// let mut batch = OpenBatch::with_options(OpenOptions::new().shell(WindowsShell::Cmd).clone());
+    /// for i in 0..50 {
+    ///     batch.add(format!("photo-{i}.jpg"));
+    /// }
+    ///
+    /// let cmds = batch.commands();
+    /// assert_eq!(cmds.len(), 1);
+    /// assert_eq!(cmds[0].get_program(), "cmd.exe");
+    ///
+    /// // Dropping the batch would open its targets, so discard them.
+    /// batch.clear();
+    /// ```
+    pub fn commands(&self) -> Vec<Command> {
+        let targets: Vec<_> = self
+            .targets
+            .iter()
+            .map(|target| self.options.expand(target))
+            .collect();
+        batch_commands(&targets, &self.options)
+    }
+
+    /// Opens all targets in the batch and empties it.
+    ///
+    /// Targets are checked and expanded like those opened by [`OpenOptions::that()`], and every
+    /// command is run, even if an earlier one fails. With [`OpenOptions::kill_on_exit()`],
+    /// the launchers are detached and assigned to its job rather than waited for, like those of
+    /// [`OpenOptions::that_detached()`].
+    ///
+    /// # Examples
+    ///
+    /// Open many targets in a batch, spawning far fewer shells than targets.
+    ///
+    /// ```
+    /// # #[cfg(feature = "mock")]
+    /// # {
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use win_open::{mock, OpenBatch, OpenOptions, WindowsShell};
+    ///
+    /// static SPAWNED: AtomicUsize = AtomicUsize::new(0);
+    /// mock::set_runner(|_| {
+    ///     SPAWNED.fetch_add(1, Ordering::SeqCst);
+    ///     Ok(mock::exit_status(0))
+    /// });
+    ///
+    /// let mut batch = OpenBatch::with_options(OpenOptions::new().shell(WindowsShell::Cmd).clone());
+    /// for i in 0..50 {
+    ///     batch.add(format!("https://example.com/{i}"));
+    /// }
+    /// batch.flush().unwrap();
+    /// mock::clear_runner();
+    ///
+    /// assert!(batch.is_empty());
+    /// assert_eq!(SPAWNED.load(Ordering::SeqCst), 1);
+    /// # }
+    /// ```
+    ///
+    /// Device paths are rejected, and `%VAR%` is expanded if enabled, as for a single target.
+    ///
+    /// ```
+    /// # #[cfg(feature = "mock")]
+    /// # {
+    /// use std::{cell::RefCell, rc::Rc};
+    /// use win_open::{mock, ErrorKind, OpenBatch, OpenOptions, WindowsShell};
+    ///
+    /// let args = Rc::new(RefCell::new(Vec::new()));
+    /// let recorded = Rc::clone(&args);
+    /// mock::set_runner(move |cmd| {
+    ///     recorded.borrow_mut().extend(cmd.get_args().map(|arg| arg.to_os_string()));
+    ///     Ok(mock::exit_status(0))
+    /// });
+    /// let mut options = OpenOptions::new();
+    /// options.shell(WindowsShell::Cmd).expand_path(true);
+    /// let mut batch = OpenBatch::with_options(options);
+    /// batch.add(r"%SystemRoot%\win.ini").add(r"\\.\PhysicalDrive0");
+    /// let result = batch.flush();
+    /// mock::clear_runner();
+    ///
+    /// assert_eq!(result.unwrap_err().kind(), &ErrorKind::INVALID_INPUT);
+    /// let root = std::env::var("SystemRoot").unwrap();
+    /// let script = args.borrow().last().unwrap().to_string_lossy().into_owned();
+    /// assert!(script.contains(&format!(r"{root}\win.ini")));
+    /// assert!(!script.contains("PhysicalDrive0"));
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// The first [`Error`](crate::Error) is returned if a target can't be resolved,
+    /// or one of the commands fails.
+    pub fn flush(&mut self) -> Result<()> {
+        let mut first_err = None;
+        let mut targets = Vec::with_capacity(self.targets.len());
+        for target in std::mem::take(&mut self.targets) {
+            match crate::checked_target(&self.options.expand(&target), &self.options) {
+                Ok(target) => targets.push(target),
+                Err(err) => {
+                    first_err.get_or_insert(err);
+                }
+            }
+        }
+
+        // `ShellExecuteExW` spawns no shell at all, so there's nothing to batch.
+        // Only launchers spawned as commands can be assigned to a job, though.
+        #[cfg(feature = "shellexecute-open")]
+        if crate::uses_execute(&self.options) && !self.options.kills_on_exit() {
+            for target in targets {
+                if let Err(err) = crate::that_with_options(target, &self.options) {
+                    first_err.get_or_insert(err);
+                }
+            }
+            return first_err.map_or(Ok(()), Err);
+        }
+
+        for mut cmd in batch_commands(&targets, &self.options) {
+            #[cfg(feature = "shellexecute-open")]
+            if self.options.kills_on_exit() {
+                let result = match cmd.spawn_detached() {
+                    Ok(Some(child)) => crate::job::kill_on_exit(&child),
+                    Ok(None) => Ok(()),
+                    Err(err) => Err(err.into()),
+                };
+                if let Err(err) = result {
+                    first_err.get_or_insert(err);
+                }
+                continue;
+            }
+
+            let status = cmd.status_with_stdio(self.options.get_stdio());
+            if let Err(err) = status.into_result(cmd) {
+                first_err.get_or_insert(err);
+            }
+        }
+        first_err.map_or(Ok(()), Err)
+    }
+}
+
+impl Drop for OpenBatch {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// Builds the commands opening `targets`, chaining as many as possible into each shell script.
+fn batch_commands(targets: &[OsString], options: &OpenOptions) -> Vec<Command> {
+    if targets.is_empty() {
+        return Vec::new();
+    }
+
+    let shell = options.resolve_shell();
+    let mut cmds = Vec::new();
+    let mut script = OsString::new();
+    for target in targets {
+        // These aren't handled by every shell, so they keep their dedicated launcher.
+        if target::control_panel_item(target).is_some() || target::is_app_uri(target) {
+            cmds.extend(
+                crate::commands_with_options(target, options)
+                    .into_iter()
+                    .take(1),
+            );
+            continue;
+        }
+
+        let step = script_step(target, shell);
+        if !script.is_empty() && script.len() + step.len() > MAX_SCRIPT_LEN {
+            cmds.push(script_command(std::mem::take(&mut script), shell, options));
+        }
+        if !script.is_empty() {
+            script.push(match shell {
+                WindowsShell::Cmd => " & ",
+                WindowsShell::Powershell | WindowsShell::Nushell => "; ",
+            });
+        }
+        script.push(step);
+    }
+    if !script.is_empty() {
+        cmds.push(script_command(script, shell, options));
+    }
+    cmds
+}
+
+/// The part of a script opening `target` with `shell`.
+fn script_step(target: &OsStr, shell: WindowsShell) -> OsString {
+    match shell {
+        WindowsShell::Powershell => {
+            let mut step = OsString::from("Start-Process ");
+            step.push(powershell_string_literal(target));
+            step
+        }
+        WindowsShell::Nushell => format!("open {}", nu_string_literal(target)).into(),
+        WindowsShell::Cmd => {
+            let mut step = OsString::from("start \"\" ");
+            step.push(wrap_in_quotes(target));
+            step
+        }
+    }
+}
+
+/// The command running `script` with `shell`.
+fn script_command(script: OsString, shell: WindowsShell, options: &OpenOptions) -> Command {
+    let mut cmd = Command::new(shell.program());
+    match shell {
+        WindowsShell::Powershell => cmd
+            .args(options.profile_args())
+            .arg("-Command")
+            .arg(script)
+            .creation_flags(CREATE_NO_WINDOW),
+        WindowsShell::Nushell => cmd.arg("-c").arg(script).creation_flags(CREATE_NO_WINDOW),
+        WindowsShell::Cmd => cmd
+            .arg("/c")
+            .args(options.codepage_args())
+            .raw_arg(script)
+            .creation_flags(CREATE_NO_WINDOW),
+    };
+    cmd
+}
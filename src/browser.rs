@@ -0,0 +1,169 @@
+use std::process::Command;
+
+use crate::CommandRunExt;
+
+/// The registry key holding the user's choice of default browser.
+const USER_CHOICE_KEY: &str =
+    r"HKCU\Software\Microsoft\Windows\Shell\Associations\UrlAssociations\https\UserChoice";
+
+/// Enum representing the families of web browsers, used to pick browser specific command line flags.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum BrowserFamily {
+    /// Google Chrome (`chrome`).
+    Chrome,
+
+    /// Microsoft Edge (`msedge`).
+    Edge,
+
+    /// Other Chromium based browsers, such as Brave, Vivaldi or Opera.
+    Chromium,
+
+    /// Mozilla Firefox (`firefox`).
+    Firefox,
+
+    /// A browser whose family could not be determined.
+    Unknown,
+}
+
+impl BrowserFamily {
+    /// Detects the family of the user's default web browser.
+    ///
+    /// The `ProgId` of the user's choice for `https` URLs is read from the registry.
+    ///
+    /// # Returns
+    /// The family of the default browser, or `BrowserFamily::Unknown` if it could not be determined.
+    pub fn detect() -> Self {
+        let output = Command::new("reg")
+            .arg("query")
+            .arg(USER_CHOICE_KEY)
+            .arg("/v")
+            .arg("ProgId")
+            .query_output();
+
+        match output {
+            Ok(output) if output.status.success() => {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                stdout
+                    .lines()
+                    .find_map(|line| line.trim().strip_prefix("ProgId"))
+                    .and_then(|line| line.split_whitespace().last())
+                    .map_or(BrowserFamily::Unknown, BrowserFamily::from_prog_id)
+            }
+            _ => BrowserFamily::Unknown,
+        }
+    }
+
+    /// Determines the browser family from a `ProgId`, such as `ChromeHTML` or `FirefoxURL-308046B0AF4A39CB`.
+    fn from_prog_id(prog_id: &str) -> Self {
+        let prog_id = prog_id.to_ascii_uppercase();
+        if prog_id.starts_with("CHROMEHTML") {
+            BrowserFamily::Chrome
+        } else if prog_id.starts_with("MSEDGEHTM") {
+            BrowserFamily::Edge
+        } else if prog_id.starts_with("FIREFOXURL") {
+            BrowserFamily::Firefox
+        } else if ["BRAVEHTML", "VIVALDIHTM", "OPERA", "CHROMIUMHTM"]
+            .iter()
+            .any(|prefix| prog_id.starts_with(prefix))
+        {
+            BrowserFamily::Chromium
+        } else {
+            BrowserFamily::Unknown
+        }
+    }
+
+    /// Returns the executable that launches a browser of this family.
+    ///
+    /// # Returns
+    /// The executable name (e.g. "chrome", "msedge", "firefox"),
+    /// or `None` for `BrowserFamily::Chromium` and `BrowserFamily::Unknown`.
+    pub fn executable(self) -> Option<&'static str> {
+        match self {
+            BrowserFamily::Chrome => Some("chrome"),
+            BrowserFamily::Edge => Some("msedge"),
+            BrowserFamily::Firefox => Some("firefox"),
+            BrowserFamily::Chromium | BrowserFamily::Unknown => None,
+        }
+    }
+
+    /// Returns the command line flag that opens a private (incognito) window in a browser of this family.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use win_open::BrowserFamily;
+    ///
+    /// assert_eq!(BrowserFamily::Chrome.private_flag(), Some("--incognito"));
+    /// assert_eq!(BrowserFamily::Edge.private_flag(), Some("--inprivate"));
+    /// assert_eq!(BrowserFamily::Firefox.private_flag(), Some("-private-window"));
+    /// assert_eq!(BrowserFamily::Unknown.private_flag(), None);
+    /// ```
+    ///
+    /// # Returns
+    /// The flag, or `None` if the private mode of the family isn't known.
+    pub fn private_flag(self) -> Option<&'static str> {
+        match self {
+            BrowserFamily::Chrome | BrowserFamily::Chromium => Some("--incognito"),
+            BrowserFamily::Edge => Some("--inprivate"),
+            BrowserFamily::Firefox => Some("-private-window"),
+            BrowserFamily::Unknown => None,
+        }
+    }
+
+    /// Returns the command line flag that makes a browser of this family use `proxy`.
+    ///
+    /// Firefox has no such flag, as it reads the proxy from the settings of its profile.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use win_open::BrowserFamily;
+    ///
+    /// let flag = BrowserFamily::Chrome.proxy_flag("proxy.corp:8080");
+    /// assert_eq!(flag.as_deref(), Some("--proxy-server=proxy.corp:8080"));
+    /// assert_eq!(BrowserFamily::Firefox.proxy_flag("proxy.corp:8080"), None);
+    /// ```
+    ///
+    /// # Parameters
+    /// - `proxy`: The proxy, such as `proxy.corp:8080` or `socks5://127.0.0.1:1080`.
+    ///
+    /// # Returns
+    /// The flag, or `None` if the family has no proxy flag.
+    pub fn proxy_flag(self, proxy: &str) -> Option<String> {
+        match self {
+            BrowserFamily::Chrome | BrowserFamily::Edge | BrowserFamily::Chromium => {
+                Some(format!("--proxy-server={proxy}"))
+            }
+            BrowserFamily::Firefox | BrowserFamily::Unknown => None,
+        }
+    }
+}
+
+/// Counts the running processes of `executable` (e.g. "chrome"), using `tasklist`.
+///
+/// # Returns
+/// The number of processes, or `None` if they could not be listed.
+pub(crate) fn count_processes(executable: &str) -> Option<usize> {
+    let image = format!("{executable}.exe");
+    let output = Command::new("tasklist")
+        .arg("/FI")
+        .arg(format!("IMAGENAME eq {image}"))
+        .arg("/FO")
+        .arg("CSV")
+        .arg("/NH")
+        .query_output()
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    // Each process is listed on a line starting with its quoted image name.
+    let quoted = format!("\"{image}\"");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Some(
+        stdout
+            .lines()
+            .filter(|line| {
+                line.len() >= quoted.len() && line[..quoted.len()].eq_ignore_ascii_case(&quoted)
+            })
+            .count(),
+    )
+}
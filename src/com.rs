@@ -0,0 +1,40 @@
+use std::ffi::c_void;
+
+use crate::{ffi, Error, Result};
+
+/// An owned pointer to a COM interface with the vtable `V`, released on drop.
+pub(crate) struct ComPtr<V>(pub(crate) *mut *const V);
+
+impl<V> ComPtr<V> {
+    /// Returns the vtable of the interface.
+    ///
+    /// # Safety
+    /// The pointer must point to a live COM object implementing the interface.
+    pub(crate) unsafe fn vtable(&self) -> &V {
+        &**self.0
+    }
+
+    /// Returns the `this` pointer passed to the methods of the interface.
+    pub(crate) fn this(&self) -> *mut c_void {
+        self.0.cast()
+    }
+}
+
+impl<V> Drop for ComPtr<V> {
+    fn drop(&mut self) {
+        // Every COM vtable starts with the methods of `IUnknown`.
+        unsafe {
+            let vtable = &**self.0.cast::<*const ffi::IUnknownVtbl>();
+            (vtable.Release)(self.this());
+        }
+    }
+}
+
+/// Converts an `HRESULT` into a `Result`, treating negative values as failure.
+pub(crate) fn check(hresult: i32) -> Result<()> {
+    if hresult < 0 {
+        Err(Error::from_os_error(hresult))
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+use std::ffi::OsStr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{Error, ErrorKind, Result};
+
+/// The number of data URIs written to temporary files by this process, to name them uniquely.
+static WRITTEN: AtomicUsize = AtomicUsize::new(0);
+
+/// Writes the file represented by the `data:` URI `uri` to a temporary file.
+///
+/// Only URIs with a base64 payload and a known mime type represent files,
+/// e.g. `data:text/html;base64,PGgxPkhpPC9oMT4=`. The extension of the file is derived
+/// from the mime type, so it's opened with the right application.
+///
+/// # Returns
+/// The path of the temporary file, or `None` if `uri` doesn't represent a file.
+pub(crate) fn to_temp_file(uri: &OsStr) -> Result<Option<PathBuf>> {
+    let uri = uri.to_string_lossy();
+    let Some((header, payload)) = uri
+        .get(..5)
+        .filter(|scheme| scheme.eq_ignore_ascii_case("data:"))
+        .and_then(|_| uri[5..].split_once(','))
+    else {
+        return Ok(None);
+    };
+
+    let mut params = header.split(';');
+    let mime = params.next().unwrap_or_default().to_ascii_lowercase();
+    if !params.any(|param| param.eq_ignore_ascii_case("base64")) {
+        return Ok(None);
+    }
+    let Some(extension) = extension(&mime) else {
+        return Ok(None);
+    };
+
+    let data = decode_base64(payload).ok_or_else(|| {
+        Error::new(
+            ErrorKind::INVALID_INPUT,
+            "the data URI has an invalid base64 payload",
+        )
+    })?;
+
+    let dir = std::env::temp_dir().join("win-open");
+    std::fs::create_dir_all(&dir)?;
+    let file = dir.join(format!(
+        "data-{}-{}.{extension}",
+        std::process::id(),
+        WRITTEN.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::write(&file, data)?;
+    Ok(Some(file))
+}
+
+/// The extension of files of the type `mime`, if known.
+fn extension(mime: &str) -> Option<&'static str> {
+    Some(match mime {
+        "text/html" => "html",
+        "text/plain" => "txt",
+        "text/css" => "css",
+        "text/csv" => "csv",
+        "text/xml" | "application/xml" => "xml",
+        "application/json" => "json",
+        "application/pdf" => "pdf",
+        "application/zip" => "zip",
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/bmp" => "bmp",
+        "image/svg+xml" => "svg",
+        "image/x-icon" | "image/vnd.microsoft.icon" => "ico",
+        "audio/mpeg" => "mp3",
+        "audio/wav" => "wav",
+        "video/mp4" => "mp4",
+        _ => return None,
+    })
+}
+
+/// Decodes standard base64, ignoring whitespace and tolerating missing padding.
+///
+/// # Returns
+/// The decoded bytes, or `None` if `input` isn't valid base64.
+fn decode_base64(input: &str) -> Option<Vec<u8>> {
+    let mut output = Vec::with_capacity(input.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in input.bytes().filter(|c| !c.is_ascii_whitespace()) {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((buffer >> bits) as u8);
+        }
+    }
+    Some(output)
+}
@@ -1,6 +1,22 @@
 /// Enum representing various types of errors that may occur in a shell operation.
-#[derive(Debug, PartialEq)]
+///
+/// # Examples
+///
+/// Count failures by kind, e.g. for telemetry.
+///
+/// ```
+/// use std::collections::HashMap;
+/// use win_open::ErrorKind;
+///
+/// let mut failures: HashMap<ErrorKind, u64> = HashMap::new();
+/// for kind in [ErrorKind::IO, ErrorKind::COMMAND_FAILED, ErrorKind::IO] {
+///     *failures.entry(kind).or_default() += 1;
+/// }
+/// assert_eq!(failures[&ErrorKind::IO], 2);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[allow(non_camel_case_types)] // To allow the use of all-uppercase error kind variants
+#[non_exhaustive]
 pub enum ErrorKind {
     /// Error indicating that a shell type was not found or recognized.
     SHELL_NOT_FOUND,
@@ -13,13 +29,29 @@ pub enum ErrorKind {
 
     /// Error indicating an I/O operation failure.
     IO,
+
+    /// Error indicating that the requested application or path could not be found.
+    NOT_FOUND,
+
+    /// Error indicating that the operation was cancelled, e.g. by declining a UAC prompt.
+    CANCELLED,
+
+    /// Error indicating that an input, such as a path or an application, is invalid.
+    INVALID_INPUT,
+
+    /// Error indicating that a shell exists, but its detection probe failed, e.g. because it crashed on launch.
+    PROBE_FAILED,
+
+    /// Error indicating that no application or file type is associated with a path or extension.
+    NO_ASSOCIATION,
 }
 
 /// A struct representing an error in shell operations.
 /// It includes the type of the error (`ErrorKind`) and an optional message.
 pub struct Error {
-    kind: ErrorKind, // The type of the error (e.g., I/O, command failure)
-    message: String, // An optional message describing the error
+    kind: ErrorKind,                // The type of the error (e.g., I/O, command failure)
+    message: String,                // An optional message describing the error
+    source: Option<std::io::Error>, // The underlying I/O error, if any
 }
 
 impl PartialEq for Error {
@@ -56,6 +88,7 @@ impl Error {
             Self {
                 kind,
                 message: message.to_string(),
+                source: None,
             }
         }
     }
@@ -71,6 +104,7 @@ impl Error {
         Self {
             kind,
             message: "".to_string(),
+            source: None,
         }
     }
 
@@ -82,6 +116,61 @@ impl Error {
         &self.kind
     }
 
+    /// Returns `true` if the error is of kind [`ErrorKind::SHELL_NOT_FOUND`].
+    pub fn is_shell_not_found(&self) -> bool {
+        self.kind == ErrorKind::SHELL_NOT_FOUND
+    }
+
+    /// Returns `true` if the error is of kind [`ErrorKind::COMMAND_FAILED`].
+    pub fn is_command_failed(&self) -> bool {
+        self.kind == ErrorKind::COMMAND_FAILED
+    }
+
+    /// Returns `true` if the error is of kind [`ErrorKind::NO_LAUNCHER`].
+    pub fn is_no_launcher(&self) -> bool {
+        self.kind == ErrorKind::NO_LAUNCHER
+    }
+
+    /// Returns `true` if the error is of kind [`ErrorKind::IO`].
+    pub fn is_io(&self) -> bool {
+        self.kind == ErrorKind::IO
+    }
+
+    /// Returns `true` if the error is of kind [`ErrorKind::NOT_FOUND`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use win_open::{Error, ErrorKind};
+    ///
+    /// let err = Error::new(ErrorKind::NOT_FOUND, "notepad++");
+    /// assert!(err.is_not_found());
+    /// assert!(!err.is_io());
+    /// ```
+    pub fn is_not_found(&self) -> bool {
+        self.kind == ErrorKind::NOT_FOUND
+    }
+
+    /// Returns `true` if the error is of kind [`ErrorKind::CANCELLED`].
+    pub fn is_cancelled(&self) -> bool {
+        self.kind == ErrorKind::CANCELLED
+    }
+
+    /// Returns `true` if the error is of kind [`ErrorKind::INVALID_INPUT`].
+    pub fn is_invalid_input(&self) -> bool {
+        self.kind == ErrorKind::INVALID_INPUT
+    }
+
+    /// Returns `true` if the error is of kind [`ErrorKind::PROBE_FAILED`].
+    pub fn is_probe_failed(&self) -> bool {
+        self.kind == ErrorKind::PROBE_FAILED
+    }
+
+    /// Returns `true` if the error is of kind [`ErrorKind::NO_ASSOCIATION`].
+    pub fn is_no_association(&self) -> bool {
+        self.kind == ErrorKind::NO_ASSOCIATION
+    }
+
     /// Retrieves the error message, if provided.
     ///
     /// # Returns
@@ -89,6 +178,137 @@ impl Error {
     pub fn message(&self) -> &str {
         self.message.as_str()
     }
+
+    /// Attaches additional context to the error, such as the user action that triggered it.
+    ///
+    /// The context is prepended to the message, while the kind and the underlying I/O error are preserved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use win_open::{Error, ErrorKind};
+    ///
+    /// let err = Error::new(ErrorKind::COMMAND_FAILED, "exit code: 1").context("opening the manual");
+    /// assert_eq!(err.to_string(), "Command failed (opening the manual: exit code: 1)");
+    /// assert_eq!(err.kind(), &ErrorKind::COMMAND_FAILED);
+    /// ```
+    ///
+    /// # Parameters
+    /// - `context`: The context to attach.
+    ///
+    /// # Returns
+    /// The error with the context attached.
+    pub fn context(mut self, context: impl Into<String>) -> Self {
+        let context = context.into();
+        self.message = if self.message.is_empty() {
+            context
+        } else {
+            format!("{}: {}", context, self.message)
+        };
+        self
+    }
+
+    /// Replaces the kind of the error, keeping its message and underlying I/O error.
+    pub(crate) fn with_kind(mut self, kind: ErrorKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Creates an error from the `SE_ERR_*` code that `ShellExecuteExW` and `ShellExecuteW`
+    /// report in `hInstApp`, which carries a more precise reason than `GetLastError`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use win_open::{Error, ErrorKind};
+    ///
+    /// const SE_ERR_NOASSOC: isize = 31;
+    /// let err = Error::from_shell_execute_code(SE_ERR_NOASSOC).unwrap();
+    /// assert_eq!(err.kind(), &ErrorKind::NO_ASSOCIATION);
+    ///
+    /// assert!(Error::from_shell_execute_code(42).is_none());
+    /// ```
+    ///
+    /// # Parameters
+    /// - `code`: The value of `hInstApp`.
+    ///
+    /// # Returns
+    /// The error, or `None` if `code` doesn't indicate an error (i.e. it's `0` or greater than 32).
+    #[cfg(feature = "shellexecute-open")]
+    pub fn from_shell_execute_code(code: isize) -> Option<Self> {
+        let (kind, message) = match code {
+            2 => (ErrorKind::NOT_FOUND, "the file was not found"),
+            3 => (ErrorKind::NOT_FOUND, "the path was not found"),
+            5 => (ErrorKind::IO, "access denied"),
+            8 => (ErrorKind::IO, "not enough memory to complete the operation"),
+            11 => (ErrorKind::INVALID_INPUT, "the executable is invalid"),
+            26 => (ErrorKind::IO, "a sharing violation occurred"),
+            27 => (
+                ErrorKind::NO_LAUNCHER,
+                "the file association is incomplete or invalid",
+            ),
+            28..=30 => (ErrorKind::COMMAND_FAILED, "the DDE transaction failed"),
+            31 => (
+                ErrorKind::NO_ASSOCIATION,
+                "no application is associated with the file",
+            ),
+            32 => (ErrorKind::NOT_FOUND, "the specified DLL was not found"),
+            1..=32 => (ErrorKind::COMMAND_FAILED, "ShellExecute failed"),
+            _ => return None,
+        };
+        Some(Self::new(kind, format!("{message} (SE_ERR {code})")))
+    }
+
+    /// Creates an error of kind [`ErrorKind::IO`] from a raw OS error code, e.g. one returned by FFI.
+    ///
+    /// The message is the system's description of the code, which is kept as the source,
+    /// so it's available from [`Error::io_code()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use win_open::{Error, ErrorKind};
+    ///
+    /// const ERROR_ACCESS_DENIED: i32 = 5;
+    /// let err = Error::from_os_error(ERROR_ACCESS_DENIED);
+    /// assert_eq!(err.kind(), &ErrorKind::IO);
+    /// assert_eq!(err.io_code(), Some(ERROR_ACCESS_DENIED));
+    /// assert_eq!(err.message(), std::io::Error::from_raw_os_error(5).to_string());
+    /// ```
+    ///
+    /// # Parameters
+    /// - `code`: The OS error code, such as a Win32 error code or an `HRESULT`.
+    pub fn from_os_error(code: i32) -> Self {
+        std::io::Error::from_raw_os_error(code).into()
+    }
+
+    /// Creates an error of kind [`ErrorKind::IO`] from the last OS error of the current thread.
+    pub(crate) fn last_os_error() -> Self {
+        std::io::Error::last_os_error().into()
+    }
+
+    /// Retrieves the raw OS error code of the underlying I/O error, if any.
+    ///
+    /// This allows branching on specific Windows error codes
+    /// (e.g. `2` for "file not found" or `5` for "access denied") without matching on messages.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let err = win_open::Error::from(std::io::Error::from_raw_os_error(5));
+    /// assert_eq!(err.io_code(), Some(5));
+    /// ```
+    ///
+    /// # Returns
+    /// `Some(code)` if the error wraps an OS error, `None` otherwise.
+    pub fn io_code(&self) -> Option<i32> {
+        self.source.as_ref().and_then(std::io::Error::raw_os_error)
+    }
+
+    /// Returns the kind of the underlying I/O error, if any.
+    pub(crate) fn io_kind(&self) -> Option<std::io::ErrorKind> {
+        self.source.as_ref().map(std::io::Error::kind)
+    }
 }
 
 impl core::fmt::Display for ErrorKind {
@@ -109,6 +329,21 @@ impl core::fmt::Display for ErrorKind {
             ErrorKind::IO => {
                 write!(f, "IO Error")
             }
+            ErrorKind::NOT_FOUND => {
+                write!(f, "Not found")
+            }
+            ErrorKind::CANCELLED => {
+                write!(f, "Operation cancelled")
+            }
+            ErrorKind::INVALID_INPUT => {
+                write!(f, "Invalid input")
+            }
+            ErrorKind::PROBE_FAILED => {
+                write!(f, "Shell probe failed")
+            }
+            ErrorKind::NO_ASSOCIATION => {
+                write!(f, "No association")
+            }
         }
     }
 }
@@ -144,6 +379,8 @@ impl core::fmt::Display for Error {
 impl From<std::io::Error> for Error {
     /// Converts a `std::io::Error` into an `Error` with the `IO` error kind and the error message.
     ///
+    /// An `Error` wrapped in the I/O error, such as one returned by the pre-open hook, is returned as-is.
+    ///
     /// This allows for easy conversion from I/O errors (e.g., file or network errors) to our custom `Error` type.
     ///
     /// # Parameters
@@ -152,13 +389,67 @@ impl From<std::io::Error> for Error {
     /// # Returns
     /// A new `Error` instance with the `IO` error kind and the I/O error message.
     fn from(err: std::io::Error) -> Self {
-        Self::new(ErrorKind::IO, err.to_string().as_str())
+        // Errors of this crate may be passed through I/O errors, e.g. by the pre-open hook, so unwrap them.
+        if err.get_ref().is_some_and(|inner| inner.is::<Error>()) {
+            let inner = err.into_inner().expect("checked above");
+            return *inner.downcast::<Error>().expect("checked above");
+        }
+
+        let message = err.to_string();
+        Self {
+            source: Some(err),
+            ..Self::new(ErrorKind::IO, message)
+        }
     }
 }
 
-impl std::error::Error for Error {} // Implements the standard error trait for compatibility with other error handling mechanisms.
+impl std::error::Error for Error {
+    /// Returns the underlying I/O error, if any.
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_ref()
+            .map(|err| err as &(dyn std::error::Error + 'static))
+    }
+}
 
 /// A custom `Result` type that returns `Error` in case of failure.
 ///
 /// This type is used for handling errors related to shell operations. It wraps the standard `Result` type but replaces the error type with our custom `Error` type.
 pub type Result<T> = core::result::Result<T, Error>;
+
+impl From<Error> for std::io::Error {
+    /// Converts an `Error` into a `std::io::Error`, for interop with APIs expecting `std::io::Result`.
+    ///
+    /// `IO` errors are converted back to their underlying I/O error, keeping its `std::io::ErrorKind`
+    /// and raw OS error code. Other kinds are wrapped in an I/O error of kind `std::io::ErrorKind::Other`,
+    /// and are returned as-is when converted back into an `Error`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use win_open::{Error, ErrorKind};
+    ///
+    /// let err = std::io::Error::from(Error::new(ErrorKind::COMMAND_FAILED, "exit code: 1"));
+    /// assert_eq!(err.kind(), std::io::ErrorKind::Other);
+    ///
+    /// let err = std::io::Error::from(Error::from(std::io::Error::from_raw_os_error(2)));
+    /// assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    /// assert_eq!(err.raw_os_error(), Some(2));
+    /// ```
+    ///
+    /// # Parameters
+    /// - `err`: The `Error` to convert.
+    ///
+    /// # Returns
+    /// A `std::io::Error` representing the error.
+    fn from(err: Error) -> Self {
+        match err {
+            Error {
+                kind: ErrorKind::IO,
+                source: Some(source),
+                ..
+            } => source,
+            err => std::io::Error::other(err),
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Quote arguments for the shells used to open targets.
+//!
+//! This is the escaping the crate applies to the paths, URLs and apps it passes to a shell,
+//! exposed for building your own commands. [`escape_for()`] returns a single token of the
+//! shell's syntax, which is literal to the shell.
+//!
+//! How the token is passed depends on the shell. `cmd` doesn't parse its command line like
+//! other programs, so the token must be passed with
+//! [`CommandExt::raw_arg()`](std::os::windows::process::CommandExt::raw_arg). PowerShell and
+//! Nushell receive a script, so the token is part of the argument following `-Command` or `-c`,
+//! passed with [`Command::arg()`](std::process::Command::arg).
+//!
+//! # Limitations
+//!
+//! `cmd` has no way to escape a `"` within a quoted token, and still expands environment
+//! variables such as `%PATH%` within it. Windows paths can't contain `"`, but URLs and other
+//! arguments may need to be percent-encoded before being passed to `cmd`.
+//!
+//! # Examples
+//!
+//! Special characters of `cmd` are quoted.
+//!
+//! ```
+//! use std::ffi::OsStr;
+//! use win_open::{escape::escape_for, WindowsShell};
+//!
+//! let arg = escape_for(WindowsShell::Cmd, OsStr::new(r"C:\R&D\a|b <c> ^d.txt"));
+//! assert_eq!(arg, r#""C:\R&D\a|b <c> ^d.txt""#);
+//! ```
+//!
+//! PowerShell variables, subexpressions and quotes are escaped with a backtick,
+//! including the typographic quotes PowerShell also accepts.
+//!
+//! ```
+//! use std::ffi::OsStr;
+//! use win_open::{escape::escape_for, WindowsShell};
+//!
+//! let arg = escape_for(WindowsShell::Powershell, OsStr::new(r"C:\$(calc)\$env:USERNAME.txt"));
+//! assert_eq!(arg, r#""C:\`$(calc)\`$env:USERNAME.txt""#);
+//!
+//! let arg = escape_for(WindowsShell::Powershell, OsStr::new("a\"b`c\u{201C}d"));
+//! assert_eq!(arg, "\"a`\"b``c`\u{201C}d\"");
+//!
+//! // Single quotes need no escaping within double quotes.
+//! let arg = escape_for(WindowsShell::Powershell, OsStr::new(r"C:\O'Brien's notes.txt"));
+//! assert_eq!(arg, r#""C:\O'Brien's notes.txt""#);
+//! ```
+//!
+//! Nushell strings are single quoted, so backslashes and `$` are literal. Values containing
+//! a single quote use a raw string, with enough `#` to not be closed early.
+//!
+//! ```
+//! use std::ffi::OsStr;
+//! use win_open::{escape::escape_for, WindowsShell};
+//!
+//! let arg = escape_for(WindowsShell::Nushell, OsStr::new(r"C:\new\$(x) (1).txt"));
+//! assert_eq!(arg, r"'C:\new\$(x) (1).txt'");
+//!
+//! let arg = escape_for(WindowsShell::Nushell, OsStr::new("O'Brien's.txt"));
+//! assert_eq!(arg, "r#'O'Brien's.txt'#");
+//!
+//! let arg = escape_for(WindowsShell::Nushell, OsStr::new("a'#b"));
+//! assert_eq!(arg, "r##'a'#b'##");
+//! ```
+
+use std::ffi::{OsStr, OsString};
+
+use crate::WindowsShell;
+
+/// Quotes `arg` as a single literal token for `shell`.
+///
+/// See the [module documentation](self) for how to pass the token, and the limitations of `cmd`.
+///
+/// # Parameters
+/// - `shell`: The shell the token is passed to.
+/// - `arg`: The argument to quote, such as a path or URL.
+///
+/// # Returns
+/// The quoted token.
+pub fn escape_for(shell: WindowsShell, arg: &OsStr) -> OsString {
+    match shell {
+        WindowsShell::Cmd => wrap_in_quotes(arg),
+        WindowsShell::Powershell => powershell_string_literal(arg),
+        WindowsShell::Nushell => nu_string_literal(arg).into(),
+    }
+}
+
+/// Wraps `path` in double quotes, as `cmd` expects for paths containing spaces or special characters.
+pub(crate) fn wrap_in_quotes<T: AsRef<OsStr>>(path: T) -> OsString {
+    let mut result = OsString::from("\"");
+    result.push(path);
+    result.push("\"");
+
+    result
+}
+
+/// Quotes `value` as a PowerShell string literal.
+///
+/// Double quoted strings are used, as Windows paths commonly contain single quotes
+/// (e.g. `O'Brien`). Characters PowerShell would expand or end the string with are escaped
+/// with a backtick, including the typographic quotes it treats like `"`.
+pub(crate) fn powershell_string_literal<T: AsRef<OsStr>>(value: T) -> OsString {
+    let value = value.as_ref();
+    let lossy = value.to_string_lossy();
+    if !lossy.contains(['`', '$', '"', '\u{201C}', '\u{201D}', '\u{201E}']) {
+        // Keep values that need no escaping exactly as given, without a lossy conversion.
+        return wrap_in_quotes(value);
+    }
+
+    let mut result = String::with_capacity(lossy.len() + 2);
+    result.push('"');
+    for c in lossy.chars() {
+        if matches!(c, '`' | '$' | '"' | '\u{201C}' | '\u{201D}' | '\u{201E}') {
+            result.push('`');
+        }
+        result.push(c);
+    }
+    result.push('"');
+    result.into()
+}
+
+/// Quotes `value` as a Nushell string literal.
+///
+/// Nushell interprets backslash escapes in double quoted strings, which would mangle
+/// Windows paths, so single quoted strings are used. Values containing a single quote
+/// use a raw string (`r#'...'#`) instead.
+pub(crate) fn nu_string_literal<T: AsRef<OsStr>>(value: T) -> String {
+    let value = value.as_ref().to_string_lossy();
+    if !value.contains('\'') {
+        return format!("'{}'", value);
+    }
+
+    let mut hashes = String::from("#");
+    while value.contains(&format!("'{}", hashes)) {
+        hashes.push('#');
+    }
+    format!("r{hashes}'{value}'{hashes}")
+}
@@ -0,0 +1,286 @@
+use std::ffi::{c_void, OsStr};
+
+use crate::{ffi, target, wide, Error, Result, ShellExecuteExW};
+
+/// The `fMask` used by the detached execute functions.
+///
+/// As these are meant to out-live the app, wait for the shell to complete the
+/// handoff to the handler before returning, so the open isn't lost if the process exits right away.
+pub(crate) const DETACHED_EXECUTE_MASK: u32 = ffi::SEE_MASK_NOASYNC | ffi::SEE_MASK_FLAG_DDEWAIT;
+
+/// How long to wait for a launched process to be ready for input, before looking for its window.
+const FOREGROUND_TIMEOUT_MS: u32 = 5000;
+
+/// A request to `ShellExecuteExW`, owning the wide strings its `SHELLEXECUTEINFOW` points to.
+pub(crate) struct ExecuteRequest {
+    mask: u32,                    // The `fMask` flags
+    verb: Option<Vec<u16>>,       // The verb, such as `runas`, or `None` for the default verb
+    file: Vec<u16>,               // The file, URL or application to execute
+    parameters: Option<Vec<u16>>, // The parameters passed to an application
+    class: Option<Vec<u16>>,      // The file class (e.g. an extension such as `.pdf`)
+    directory: Option<Vec<u16>>,  // The working directory, or `None` to inherit it
+    show: i32,                    // How the window is shown
+    id_list: *const c_void,       // The item identifier list to open instead of `file`, or null
+    hwnd: isize,                  // The window owning dialogs shown by the shell, or 0 for none
+}
+
+impl ExecuteRequest {
+    /// Creates a request to open `file`, with the defaults of the detached execute functions.
+    pub(crate) fn new<T: AsRef<OsStr>>(file: T) -> Self {
+        Self {
+            mask: DETACHED_EXECUTE_MASK,
+            verb: None,
+            file: wide(file),
+            parameters: None,
+            class: None,
+            directory: None,
+            show: ffi::SW_SHOWNORMAL,
+            id_list: std::ptr::null(),
+            hwnd: 0,
+        }
+    }
+
+    /// Sets the verb, such as `runas` or `edit`, instead of the default verb.
+    pub(crate) fn verb<T: AsRef<OsStr>>(mut self, verb: T) -> Self {
+        self.verb = Some(wide(verb));
+        self
+    }
+
+    /// Sets the parameters passed to the application being executed.
+    pub(crate) fn parameters<T: AsRef<OsStr>>(mut self, parameters: T) -> Self {
+        self.parameters = Some(wide(parameters));
+        self
+    }
+
+    /// Sets whether the opened file is added to the recent files lists of the app and the OS.
+    pub(crate) fn log_usage(mut self, log_usage: bool) -> Self {
+        if log_usage {
+            self.mask |= ffi::SEE_MASK_FLAG_LOG_USAGE;
+        } else {
+            self.mask &= !ffi::SEE_MASK_FLAG_LOG_USAGE;
+        }
+        self
+    }
+
+    /// Sets the file class, such as an extension (`.pdf`), overriding the one of the file.
+    pub(crate) fn class<T: AsRef<OsStr>>(mut self, class: T) -> Self {
+        self.class = Some(wide(class));
+        self.mask |= ffi::SEE_MASK_CLASSNAME;
+        self
+    }
+
+    /// Sets the working directory of the launched application.
+    pub(crate) fn directory<T: AsRef<OsStr>>(mut self, directory: T) -> Self {
+        self.directory = Some(wide(directory));
+        self
+    }
+
+    /// Sets how the window is shown, as an `SW_*` value.
+    pub(crate) fn show(mut self, show: i32) -> Self {
+        self.show = show;
+        self
+    }
+
+    /// Sets the item identifier list (PIDL) to open, instead of the file.
+    ///
+    /// The list isn't copied, so it must out-live the request.
+    pub(crate) fn id_list(mut self, id_list: *const c_void) -> Self {
+        self.id_list = id_list;
+        self.mask |= ffi::SEE_MASK_IDLIST;
+        self
+    }
+
+    /// Suppresses the user interface of the shell, such as error messages, which would take focus.
+    pub(crate) fn no_ui(mut self) -> Self {
+        self.mask |= ffi::SEE_MASK_FLAG_NO_UI;
+        self
+    }
+
+    /// Sets the window owning the dialogs shown by the shell, such as UAC prompts.
+    pub(crate) fn hwnd(mut self, hwnd: isize) -> Self {
+        self.hwnd = hwnd;
+        self
+    }
+
+    /// Builds the `SHELLEXECUTEINFOW` for this request.
+    ///
+    /// The returned structure points into `self`, which must out-live it.
+    pub(crate) fn info(&self) -> ffi::SHELLEXECUTEINFOW {
+        ffi::SHELLEXECUTEINFOW {
+            cbSize: std::mem::size_of::<ffi::SHELLEXECUTEINFOW>() as _,
+            fMask: self.mask,
+            hwnd: self.hwnd,
+            nShow: self.show,
+            lpVerb: as_ptr(&self.verb),
+            lpFile: self.file.as_ptr(),
+            lpParameters: as_ptr(&self.parameters),
+            lpDirectory: as_ptr(&self.directory),
+            lpClass: as_ptr(&self.class),
+            lpIDList: self.id_list.cast_mut(),
+            ..unsafe { std::mem::zeroed() }
+        }
+    }
+
+    /// Fails if any of the strings of the request contains a NUL character,
+    /// which would silently truncate it.
+    fn check_nul(&self) -> Result<()> {
+        use std::os::windows::ffi::OsStringExt;
+
+        let values = [
+            Some(&self.file),
+            self.verb.as_ref(),
+            self.parameters.as_ref(),
+            self.class.as_ref(),
+            self.directory.as_ref(),
+        ];
+        values.into_iter().flatten().try_for_each(|value| {
+            // Skip the terminating null character added by `wide`.
+            let value = std::ffi::OsString::from_wide(&value[..value.len() - 1]);
+            target::check_nul(&value)
+        })
+    }
+
+    /// Performs the request.
+    pub(crate) fn execute(&self) -> Result<()> {
+        self.check_nul()?;
+        let mut info = self.info();
+        unsafe { ShellExecuteExW(&mut info) }
+    }
+
+    /// Performs the request, keeping a handle to the launched process.
+    ///
+    /// # Returns
+    /// The handle of the launched process, or `None` if no process was launched,
+    /// e.g. because the path was handed off to an already running instance.
+    pub(crate) fn execute_process(&self) -> Result<Option<ProcessHandle>> {
+        self.check_nul()?;
+        let mut info = self.info();
+        info.fMask |= ffi::SEE_MASK_NOCLOSEPROCESS;
+        unsafe { ShellExecuteExW(&mut info)? };
+
+        let process = info.hProcess;
+        Ok((process != 0).then_some(ProcessHandle(process)))
+    }
+}
+
+/// An owned handle to a process launched by `ShellExecuteExW`, closed on drop.
+pub(crate) struct ProcessHandle(isize);
+
+impl ProcessHandle {
+    /// Blocks until the process exits.
+    pub(crate) fn wait(&self) -> Result<()> {
+        match unsafe { ffi::WaitForSingleObject(self.0, ffi::INFINITE) } {
+            ffi::WAIT_FAILED => Err(Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the process identifier.
+    pub(crate) fn id(&self) -> u32 {
+        unsafe { ffi::GetProcessId(self.0) }
+    }
+
+    /// Attempts to bring the main window of the process to the foreground, once it's ready for input.
+    ///
+    /// # Returns
+    /// `true` if a window of the process was brought to the foreground, `false` otherwise.
+    pub(crate) fn bring_to_foreground(&self) -> bool {
+        let id = self.id();
+        unsafe {
+            ffi::WaitForInputIdle(self.0, FOREGROUND_TIMEOUT_MS);
+            ffi::AllowSetForegroundWindow(id);
+        }
+
+        match find_window(id) {
+            Some(window) => unsafe { ffi::SetForegroundWindow(window) != 0 },
+            None => false,
+        }
+    }
+}
+
+impl Drop for ProcessHandle {
+    fn drop(&mut self) {
+        unsafe { ffi::CloseHandle(self.0) };
+    }
+}
+
+/// Returns a pointer to the optional wide string, or null if absent.
+fn as_ptr(value: &Option<Vec<u16>>) -> *const u16 {
+    value
+        .as_ref()
+        .map_or(std::ptr::null(), |value| value.as_ptr())
+}
+
+/// Finds a visible top-level window belonging to the process with the given identifier.
+fn find_window(process_id: u32) -> Option<isize> {
+    // The process to look for, and the window found
+    let mut search = (process_id, None);
+
+    extern "system" fn callback(window: isize, search: isize) -> i32 {
+        let search = unsafe { &mut *(search as *mut (u32, Option<isize>)) };
+        let mut process_id = 0;
+        unsafe { ffi::GetWindowThreadProcessId(window, &mut process_id) };
+        if process_id == search.0 && unsafe { ffi::IsWindowVisible(window) } != 0 {
+            search.1 = Some(window);
+            return 0; // Stop enumerating
+        }
+        1
+    }
+
+    unsafe { ffi::EnumWindows(callback, &mut search as *mut _ as isize) };
+    search.1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn waits_for_the_handoff_to_complete() {
+        let info = ExecuteRequest::new("notes.txt").info();
+        assert_eq!(info.fMask & ffi::SEE_MASK_NOASYNC, ffi::SEE_MASK_NOASYNC);
+    }
+
+    #[test]
+    fn logs_usage_unless_disabled() {
+        let request = |options: &crate::OpenOptions| {
+            ExecuteRequest::new("notes.txt").log_usage(options.logs_usage())
+        };
+        let logged = request(&crate::OpenOptions::new()).info();
+        let unlogged = request(crate::OpenOptions::new().log_usage(false)).info();
+
+        assert_ne!(logged.fMask & ffi::SEE_MASK_FLAG_LOG_USAGE, 0);
+        assert_eq!(unlogged.fMask & ffi::SEE_MASK_FLAG_LOG_USAGE, 0);
+    }
+
+    #[test]
+    fn applies_the_file_class() {
+        let request = ExecuteRequest::new("invoice").class(".pdf");
+        let info = request.info();
+
+        assert_eq!(
+            info.fMask & ffi::SEE_MASK_CLASSNAME,
+            ffi::SEE_MASK_CLASSNAME
+        );
+        let class = unsafe { std::slice::from_raw_parts(info.lpClass, 5) };
+        assert_eq!(class, wide(".pdf"));
+    }
+
+    #[test]
+    fn applies_the_verb() {
+        let request = ExecuteRequest::new("notes.txt").verb("edit");
+        let info = request.info();
+
+        let verb = unsafe { std::slice::from_raw_parts(info.lpVerb, 5) };
+        assert_eq!(verb, wide("edit"));
+    }
+
+    #[test]
+    fn rejects_nul_characters() {
+        let err = ExecuteRequest::new("notepad.exe")
+            .parameters("a\0b")
+            .execute()
+            .unwrap_err();
+        assert_eq!(err.kind(), &crate::ErrorKind::INVALID_INPUT);
+    }
+}
@@ -0,0 +1,71 @@
+use std::path::{Path, PathBuf};
+
+use crate::{Error, ErrorKind, Result};
+
+/// Expands the wildcards `*` and `?` in the file name of `pattern`, e.g. `C:\logs\*.txt`.
+///
+/// Only the last component may contain wildcards. Like Windows itself, matching is case-insensitive.
+///
+/// # Returns
+/// The matching files, sorted by path, or an error of kind `NOT_FOUND` if nothing matched.
+pub(crate) fn expand(pattern: &Path) -> Result<Vec<PathBuf>> {
+    let not_found = || Error::new(ErrorKind::NOT_FOUND, pattern.to_string_lossy());
+
+    let name = pattern
+        .file_name()
+        .ok_or_else(not_found)?
+        .to_string_lossy()
+        .to_lowercase();
+    let dir = match pattern.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    let mut matches = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().to_lowercase();
+        if entry.file_type()?.is_file() && matches_pattern(&name, &file_name) {
+            matches.push(entry.path());
+        }
+    }
+
+    if matches.is_empty() {
+        return Err(not_found());
+    }
+    matches.sort();
+    Ok(matches)
+}
+
+/// Returns `true` if `name` matches `pattern`, where `*` matches any sequence
+/// of characters and `?` matches any single character.
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    // Iterative matching, backtracking to the most recent `*` on a mismatch.
+    let (mut p, mut n) = (0, 0);
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
@@ -0,0 +1,149 @@
+use std::os::windows::io::AsRawHandle;
+use std::process::Child;
+use std::sync::OnceLock;
+
+use crate::{ffi, Error, Result};
+
+/// `JobObjectExtendedLimitInformation`, the class of `JOBOBJECT_EXTENDED_LIMIT_INFORMATION`.
+const JOB_OBJECT_EXTENDED_LIMIT_INFORMATION_CLASS: i32 = 9;
+
+/// Terminate all processes of the job when its last handle is closed.
+const JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE: u32 = 0x2000;
+
+/// The job launchers are assigned to by [`OpenOptions::kill_on_exit()`](crate::OpenOptions::kill_on_exit).
+///
+/// Statics are never dropped, so its handle is only closed by Windows when the process exits.
+static KILL_ON_EXIT_JOB: OnceLock<JobObject> = OnceLock::new();
+
+/// A Windows job object, which terminates the processes assigned to it once it's closed.
+///
+/// The job is created with `JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE`, so its processes are terminated
+/// when the `JobObject` is dropped, or when the current process exits, even if it crashes.
+/// Processes started by an assigned process, such as the app opened by a launcher, join the job too.
+///
+/// # Examples
+///
+/// ```no_run
+/// use win_open::{CommandExt, JobObject};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let job = JobObject::new()?;
+/// let mut cmd = win_open::commands("notes.txt").remove(0);
+/// let child = cmd.detached().spawn()?;
+///
+/// job.assign(&child)?;
+/// assert!(job.contains(&child)?);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// A running process is assigned to the job, which terminates it once dropped.
+///
+/// ```
+/// use std::process::Command;
+/// use win_open::JobObject;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let job = JobObject::new()?;
+/// let mut child = Command::new("cmd.exe")
+///     .args(["/c", "ping", "-n", "2", "127.0.0.1"])
+///     .stdout(std::process::Stdio::null())
+///     .spawn()?;
+/// assert!(!job.contains(&child)?);
+///
+/// job.assign(&child)?;
+/// assert!(job.contains(&child)?);
+///
+/// drop(job);
+/// child.wait()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct JobObject(isize);
+
+// Job object handles may be used from any thread.
+unsafe impl Send for JobObject {}
+unsafe impl Sync for JobObject {}
+
+impl JobObject {
+    /// Creates an anonymous job object, terminating its processes once it's closed.
+    ///
+    /// # Errors
+    ///
+    /// A [`Error`](crate::Error) is returned if the job can't be created.
+    pub fn new() -> Result<Self> {
+        let handle = unsafe { ffi::CreateJobObjectW(std::ptr::null(), std::ptr::null()) };
+        if handle == 0 {
+            return Err(Error::last_os_error());
+        }
+        let job = JobObject(handle);
+
+        let mut info: ffi::JOBOBJECT_EXTENDED_LIMIT_INFORMATION = unsafe { std::mem::zeroed() };
+        info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+        let result = unsafe {
+            ffi::SetInformationJobObject(
+                job.0,
+                JOB_OBJECT_EXTENDED_LIMIT_INFORMATION_CLASS,
+                std::ptr::addr_of!(info).cast(),
+                std::mem::size_of_val(&info) as u32,
+            )
+        };
+        if result == 0 {
+            return Err(Error::last_os_error());
+        }
+        Ok(job)
+    }
+
+    /// Assigns `child` to the job.
+    ///
+    /// # Parameters
+    /// - `child`: The process to assign, which must still be running.
+    ///
+    /// # Errors
+    ///
+    /// A [`Error`](crate::Error) is returned if the process can't be assigned,
+    /// e.g. because it already exited.
+    pub fn assign(&self, child: &Child) -> Result<()> {
+        let process = child.as_raw_handle() as isize;
+        if unsafe { ffi::AssignProcessToJobObject(self.0, process) } == 0 {
+            return Err(Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Checks whether `child` was assigned to the job.
+    ///
+    /// # Parameters
+    /// - `child`: The process to check.
+    ///
+    /// # Errors
+    ///
+    /// A [`Error`](crate::Error) is returned if the membership can't be queried.
+    pub fn contains(&self, child: &Child) -> Result<bool> {
+        let process = child.as_raw_handle() as isize;
+        let mut result = 0;
+        if unsafe { ffi::IsProcessInJob(process, self.0, &mut result) } == 0 {
+            return Err(Error::last_os_error());
+        }
+        Ok(result != 0)
+    }
+}
+
+impl Drop for JobObject {
+    fn drop(&mut self) {
+        unsafe { ffi::CloseHandle(self.0) };
+    }
+}
+
+/// Assigns `child` to the job terminating launchers when the current process exits.
+pub(crate) fn kill_on_exit(child: &Child) -> Result<()> {
+    let job = match KILL_ON_EXIT_JOB.get() {
+        Some(job) => job,
+        None => {
+            let job = JobObject::new()?;
+            KILL_ON_EXIT_JOB.get_or_init(|| job)
+        }
+    };
+    job.assign(child)
+}
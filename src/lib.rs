@@ -65,28 +65,127 @@
 //!     Err(err) => eprintln!("An error occurred when opening '{}': {}", path, err),
 //! }
 //! ```
+//!
+//! # Features
+//!
+//! - `shellexecute-open`: Open paths with `ShellExecuteExW`, and expose
+//!   the functions built on it, such as `edit_and_wait()` and `that_foreground()`.
+//! - `shellexecute-reveal`: Open folders with `SHOpenFolderAndSelectItems`, and expose `reveal()`.
+//! - `shellexecute`: Both of the above.
+//! - `mock`: Expose the `mock` module, to test launch behavior without running real programs.
+//! - `threads` (enabled by default): Expose the functions opening in a new thread, such as
+//!   `that_in_background()`. Without it, the crate never spawns threads.
+//!
+//! Only the Win32 functions of the enabled features are compiled in.
+//!
+//! ```
+//! #[cfg(feature = "threads")]
+//! let _: fn(&'static str) -> std::thread::JoinHandle<win_open::Result<()>> =
+//!     win_open::that_in_background;
+//! // Without `threads`, opening detached is the way to avoid blocking on the launcher.
+//! #[cfg(not(feature = "threads"))]
+//! let _: fn(&'static str) -> win_open::Result<()> = win_open::that_detached;
+//! #[cfg(feature = "shellexecute-open")]
+//! let _: fn(&'static str) -> win_open::Result<()> = win_open::edit_and_wait;
+//! #[cfg(feature = "shellexecute-reveal")]
+//! let _: fn(&'static str) -> win_open::Result<()> = win_open::reveal;
+//! ```
+//!
+//! ## Launch mechanism
+//!
+//! Every entry point, such as [`that()`], [`with()`] and their `_detached` variants, uses the
+//! best mechanism available, with the following precedence:
+//!
+//! 1. A shell chosen explicitly, with [`OpenOptions::shell()`], [`with_forced_shell()`],
+//!    [`set_shell()`] or the `WIN_OPEN_SHELL` environment variable, runs a command in that shell.
+//! 2. Otherwise, with the `shellexecute-open` feature, `ShellExecuteExW` is called directly,
+//!    which is faster and doesn't spawn a shell. Control Panel items still use `control.exe`.
+//! 3. Otherwise, a command is run in the detected shell, falling back to `explorer`.
+//!
+//! Options that only apply to shells, such as [`OpenOptions::invoke_directly()`], select the shell,
+//! as does setting a runner of the `mock` module, so that the commands can be observed.
 
 #![allow(clippy::upper_case_acronyms, unused_assignments, dead_code)]
 #[cfg(not(target_os = "windows"))]
 compile_error!("open is not supported on this platform");
 
 use std::{
+    cell::Cell,
     ffi::{OsStr, OsString},
     os::windows::process::CommandExt as WinCommandExt,
-    process::{Command, Stdio},
-    sync::OnceLock,
+    process::{Command, ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        PoisonError, RwLock,
+    },
+    time::{Duration, Instant},
 };
 
+pub use app_arg::AppArg;
+#[cfg(feature = "threads")]
+pub use background::BackgroundOpen;
+pub use batch::OpenBatch;
+pub use browser::BrowserFamily;
 pub use error::Error;
-use error::ErrorKind;
+pub use error::ErrorKind;
 pub use error::Result;
+use escape::{nu_string_literal, powershell_string_literal, wrap_in_quotes};
+#[cfg(feature = "shellexecute-open")]
+use execute::ExecuteRequest;
+#[cfg(feature = "shellexecute-open")]
+pub use job::JobObject;
+pub use options::OpenOptions;
 pub use shell::WindowsShell;
+pub use spec::{OpenSpec, WindowStyle};
+pub use stdio::{StdioConfig, StdioMode};
 
+#[cfg(feature = "shellexecute-open")]
+mod activation;
+mod app;
+mod app_arg;
+#[cfg(feature = "shellexecute-open")]
+mod assoc;
+#[cfg(feature = "threads")]
+mod background;
+mod batch;
+mod browser;
+#[cfg(feature = "shellexecute-open")]
+mod com;
+mod data_uri;
 mod error;
+pub mod escape;
+#[cfg(feature = "shellexecute-open")]
+mod execute;
+mod glob;
+#[cfg(feature = "shellexecute-open")]
+mod job;
+#[cfg(feature = "mock")]
+pub mod mock;
+mod options;
+pub mod prelude;
 mod shell;
+#[cfg(feature = "shellexecute-open")]
+mod shortcut;
+mod spec;
+mod stdio;
+mod target;
 
 const CREATE_NO_WINDOW: u32 = 0x08000000;
-static DETECTED_SHELL: OnceLock<WindowsShell> = OnceLock::new();
+static DETECTED_SHELL: RwLock<Option<WindowsShell>> = RwLock::new(None);
+static SHELL_OVERRIDE: RwLock<Option<WindowsShell>> = RwLock::new(None);
+static PROBE_TIMEOUT_MS: AtomicU64 = AtomicU64::new(2000);
+static DEFAULT_APP: RwLock<Option<String>> = RwLock::new(None);
+static ALLOWED_SCHEMES: RwLock<Option<Vec<String>>> = RwLock::new(None);
+static PRE_OPEN_HOOK: RwLock<Option<PreOpenHook>> = RwLock::new(None);
+
+type PreOpenHook = Box<dyn Fn(&Command) -> Result<()> + Send + Sync>;
+static TIMING_HOOK: RwLock<Option<TimingHook>> = RwLock::new(None);
+
+type TimingHook = Box<dyn Fn(TimedStep<'_>, Duration) + Send + Sync>;
+
+thread_local! {
+    static FORCED_SHELL: Cell<Option<WindowsShell>> = const { Cell::new(None) };
+}
 
 /// Open path with the default application without blocking.
 ///
@@ -101,29 +200,263 @@ static DETECTED_SHELL: OnceLock<WindowsShell> = OnceLock::new();
 /// }
 /// ```
 ///
+/// `data:` URIs with a base64 payload and a known mime type, such as `data:image/png;base64,...`,
+/// are decoded to a temporary file, with an extension derived from the mime type, which is opened
+/// instead. Browsers limit the size of data URIs, and shells may not pass them at all.
+///
+/// ```
+/// # #[cfg(feature = "mock")]
+/// # {
+/// use std::{cell::RefCell, rc::Rc};
+/// use win_open::{mock, WindowsShell};
+///
+/// let args = Rc::new(RefCell::new(Vec::new()));
+/// let recorded = Rc::clone(&args);
+/// mock::set_runner(move |cmd| {
+///     recorded.borrow_mut().extend(cmd.get_args().map(|arg| arg.to_os_string()));
+///     Ok(mock::exit_status(0))
+/// });
+/// win_open::with_forced_shell(WindowsShell::Cmd, || {
+///     win_open::that("data:text/html;base64,PGgxPkhpPC9oMT4=").unwrap();
+/// });
+/// mock::clear_runner();
+///
+/// let file = args.borrow().last().unwrap().to_string_lossy().trim_matches('"').to_string();
+/// assert!(file.ends_with(".html"));
+/// assert_eq!(std::fs::read_to_string(&file).unwrap(), "<h1>Hi</h1>");
+/// # }
+/// ```
+///
+/// With the `shellexecute-open` feature, local paths whose names end with a dot or a space,
+/// which the shell would trim, are opened by their extended `\\?\` path.
+/// URLs are always opened unchanged.
+///
+/// ```
+/// # #[cfg(all(feature = "mock", feature = "shellexecute-open"))]
+/// # {
+/// use std::{cell::RefCell, rc::Rc};
+/// use win_open::{mock, WindowsShell};
+///
+/// let launches = Rc::new(RefCell::new(Vec::new()));
+/// let recorded = Rc::clone(&launches);
+/// mock::set_runner(move |cmd| {
+///     let args: Vec<_> = cmd.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect();
+///     recorded.borrow_mut().push(args.join(" "));
+///     Ok(mock::exit_status(0))
+/// });
+/// win_open::with_forced_shell(WindowsShell::Cmd, || {
+///     win_open::that(r"docs\..\notes.txt.").unwrap();
+///     win_open::that("https://example.com/notes.").unwrap();
+/// });
+/// mock::clear_runner();
+///
+/// let launches = launches.borrow();
+/// assert!(launches[0].contains(r"\\?\"));
+/// assert!(launches[0].contains(r"\notes.txt."));
+/// assert!(!launches[0].contains(".."));
+/// assert!(launches[1].contains("https://example.com/notes."));
+/// assert!(!launches[1].contains(r"\\?\"));
+/// # }
+/// ```
+///
 /// # Errors
 ///
-/// A [`Error`] is returned on failure. Because different operating systems
+/// A [`Error`] of kind [`ErrorKind::INVALID_INPUT`] is returned if `path` contains
+/// a NUL character, which would otherwise truncate it.
+///
+/// ```
+/// use std::ffi::OsString;
+/// use std::os::windows::ffi::OsStringExt;
+/// use win_open::ErrorKind;
+///
+/// let path = OsString::from_wide(&[b'a' as u16, 0, b'b' as u16]);
+/// let err = win_open::that(path).unwrap_err();
+/// assert_eq!(err.kind(), &ErrorKind::INVALID_INPUT);
+/// ```
+///
+/// Otherwise a [`Error`] is returned on failure. Because different operating systems
 /// handle errors differently it is recommend to not match on a certain error.
 ///
 /// # Beware
 ///
 /// Sometimes, depending on the platform and system configuration, launchers *can* block.
 /// If you want to be sure they don't, use [`that_in_background()`] or [`that_detached`] instead.
+///
+/// If a default app was set with [`set_default_app()`], the path is opened with it instead,
+/// as if by [`with()`].
 pub fn that(path: impl AsRef<OsStr>) -> Result<()> {
+    that_full(path).map(|_| ())
+}
+
+/// Open path with the default application, returning the exit status of the launcher.
+///
+/// This is like [`that()`], but keeps the status on success, e.g. to record it.
+/// When no launcher process is run, such as when opening with `ShellExecuteExW`,
+/// a successful status is returned.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let status = win_open::that_full("http://rust-lang.org")?;
+/// println!("The launcher exited with {status}");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The exit status of the launcher is kept on success.
+///
+/// ```
+/// # #[cfg(feature = "mock")]
+/// # {
+/// use win_open::{mock, WindowsShell};
+///
+/// mock::set_runner(|_| Ok(mock::exit_status(0)));
+/// let (that, with) = win_open::with_forced_shell(WindowsShell::Cmd, || {
+///     (win_open::that_full("notes.txt"), win_open::with_full("notes.txt", "notepad"))
+/// });
+/// mock::clear_runner();
+///
+/// assert_eq!(that.unwrap(), mock::exit_status(0));
+/// assert_eq!(with.unwrap(), mock::exit_status(0));
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] is returned on failure, including when the launcher exits with a nonzero status.
+/// See [`that()`] for more details.
+pub fn that_full(path: impl AsRef<OsStr>) -> Result<ExitStatus> {
+    that_with_options(path, &OpenOptions::new())
+}
+
+fn that_with_options(path: impl AsRef<OsStr>, options: &OpenOptions) -> Result<ExitStatus> {
+    let path = checked_target(path.as_ref(), options)?;
+
+    // Large data URIs aren't supported by browsers and shells, so open their contents as a file.
+    if let Some(file) = data_uri::to_temp_file(path.as_ref())? {
+        return that_with_options(file, options);
+    }
+
+    if let Some(app) = default_app() {
+        return with_with_options(path, app, options);
+    }
+
+    // App URIs are handed to their handler verbatim, so query parameters aren't mangled.
+    #[cfg(feature = "shellexecute-open")]
+    if uses_execute(options)
+        && target::is_app_uri(path.as_ref())
+        && target::control_panel_item(path.as_ref()).is_none()
+    {
+        return ExecuteRequest::new(path)
+            .verb("open")
+            .execute()
+            .map(|()| launched());
+    }
+
+    // Control Panel items are only handled by `control.exe`, so they always use the commands.
+    #[cfg(feature = "shellexecute-open")]
+    if uses_execute(options) && target::control_panel_item(&path).is_none() {
+        return that_detached_execute(path, options).map(|()| launched());
+    }
+
     let mut last_err = None;
-    for mut cmd in commands(path) {
-        match cmd.status_without_output() {
-            Ok(status) => {
-                return Ok(status).into_result(cmd);
+    for mut cmd in commands_with_options(&path, options) {
+        let status = cmd.status_with_stdio(options.get_stdio());
+        if let Err(err) = &status {
+            if redetect_missing_shell(&cmd, err, options) {
+                return that_with_options(path, options);
             }
+        }
+        match checked_status(status, cmd) {
+            Ok(status) => return Ok(status),
             Err(err) => last_err = Some(err),
         }
     }
-    Err(last_err.map_or_else(
-        || Error::new(ErrorKind::NO_LAUNCHER, ""),
-        |err| Error::new(ErrorKind::IO, err.to_string().as_str()),
-    ))
+    Err(last_err.unwrap_or_else(|| Error::new(ErrorKind::NO_LAUNCHER, "")))
+}
+
+/// Checks `path` before opening it with the default application, and resolves it.
+///
+/// Every function opening a path with the default application checks it the same way,
+/// so that none of them bypasses the device path guard or the allowed schemes.
+/// The device path guard applies to the resolved target, so neither `file://./PhysicalDrive0`
+/// nor a legacy device name such as `COM1` slips through.
+fn checked_target(path: &OsStr, options: &OpenOptions) -> Result<OsString> {
+    target::check_nul(path)?;
+    let path = target::strip_surrounding(path);
+    check_scheme(&path)?;
+    let path = target::resolve(&path)?;
+    options.check_device_path(&path)?;
+    Ok(path)
+}
+
+/// Checks `path` before passing it as-is to an application, such as a browser or an editor.
+///
+/// Like [`checked_target()`], the device path guard applies to the target the path resolves to,
+/// but the application is left to resolve it itself.
+fn check_target(path: &OsStr, options: &OpenOptions) -> Result<()> {
+    target::check_nul(path)?;
+    check_scheme(path)?;
+    options.check_device_path(path)?;
+    match target::resolve(path) {
+        Ok(resolved) => options.check_device_path(&resolved),
+        // The application reports a path it can't open.
+        Err(_) => Ok(()),
+    }
+}
+
+/// Checks `path` and `app` before opening `path` with `app`.
+fn check_with_target(path: &OsStr, app: &OsStr, options: &OpenOptions) -> Result<()> {
+    check_target(path, options)?;
+    target::check_nul(app)?;
+    options.check_app(app)
+}
+
+/// Resolve the target that [`that()`] would open for `path`, without opening it.
+///
+/// URLs are kept as-is, while local paths are made absolute, so they don't depend on
+/// the working directory of the launcher. `file://` URLs are converted back to paths,
+/// which are opened directly. This is useful for debugging reports of
+/// the wrong thing being opened.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// assert_eq!(win_open::resolve_target("https://rust-lang.org")?, "https://rust-lang.org");
+/// assert!(std::path::Path::new(&win_open::resolve_target("notes.txt")?).is_absolute());
+/// assert_eq!(win_open::resolve_target("file:///C:/Program%20Files")?, r"C:\Program Files");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// With the `shellexecute` feature, paths with trailing dots or spaces (e.g. `notes.txt `) are
+/// resolved to the extended `\\?\` form, as the shell would otherwise trim them and open
+/// a different file.
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # #[cfg(feature = "shellexecute-open")]
+/// # {
+/// let dir = std::env::temp_dir().join("win-open-trailing-space");
+/// std::fs::create_dir_all(&dir)?;
+/// // The shell would trim `notes.txt `, so the file is created through the extended form.
+/// let path = dir.join("notes.txt ");
+/// std::fs::write(format!(r"\\?\{}", path.display()), "")?;
+///
+/// let target = win_open::resolve_target(&path)?.to_string_lossy().into_owned();
+/// assert!(target.starts_with(r"\\?\") && target.ends_with(r"\notes.txt "));
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] is returned if a local path can't be resolved, e.g. because it's empty.
+pub fn resolve_target(path: impl AsRef<OsStr>) -> Result<OsString> {
+    target::resolve(path.as_ref())
 }
 
 /// Open path with the given application.
@@ -143,314 +476,3669 @@ pub fn that(path: impl AsRef<OsStr>) -> Result<()> {
 /// }
 /// ```
 ///
+/// Errors of opening with an app name the requested app.
+///
+/// ```
+/// # #[cfg(feature = "mock")]
+/// # {
+/// use win_open::mock;
+///
+/// mock::set_runner(|_| Ok(mock::exit_status(1)));
+/// let err = win_open::with("notes.txt", "my-editor").unwrap_err();
+/// assert!(err.message().contains("failed to open 'notes.txt' with 'my-editor'"));
+/// mock::clear_runner();
+/// # }
+/// ```
+///
 /// # Errors
 ///
 /// A [`Error`] is returned on failure. Because different operating systems
 /// handle errors differently it is recommend to not match on a certain error.
-pub fn with(path: impl AsRef<OsStr>, app: impl Into<String>) -> Result<()> {
-    let mut cmd = with_command(path, app);
-    cmd.status_without_output().into_result(cmd)
+pub fn with(path: impl AsRef<OsStr>, app: impl AppArg) -> Result<()> {
+    with_full(path, app).map(|_| ())
 }
 
-/// Get multiple commands that open `path` with the default application.
+/// Open path with the given application, returning the exit status of the launcher.
 ///
-/// Each command represents a launcher to try.
+/// This is like [`with()`], but keeps the status on success, e.g. to record it.
+/// When no launcher process is run, such as when opening with `ShellExecuteExW`,
+/// a successful status is returned.
 ///
 /// # Examples
 ///
 /// ```no_run
 /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
-/// let path = "http://rust-lang.org";
-/// assert!(win_open::commands(path)[0].status()?.success());
+/// let status = win_open::with_full("http://rust-lang.org", "firefox")?;
+/// println!("The launcher exited with {status}");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] is returned on failure, including when the launcher exits with a nonzero status.
+/// See [`with()`] for more details.
+pub fn with_full(path: impl AsRef<OsStr>, app: impl AppArg) -> Result<ExitStatus> {
+    with_with_options(path, app, &OpenOptions::new())
+}
+
+fn with_with_options(
+    path: impl AsRef<OsStr>,
+    app: impl AppArg,
+    options: &OpenOptions,
+) -> Result<ExitStatus> {
+    let path = &*target::strip_surrounding(path.as_ref());
+    let app = app.into_app();
+    check_with_target(path, &app, options)?;
+
+    #[cfg(feature = "shellexecute-open")]
+    if options.creates_process() && is_absolute_exe(app.as_ref()) {
+        return create_process(app.as_ref(), path).map(|()| launched());
+    }
+
+    #[cfg(feature = "shellexecute-open")]
+    let result = if uses_execute(options) {
+        with_detached_execute(path, &app).map(|()| launched())
+    } else {
+        run_with_command(path, &app, options)
+    };
+    #[cfg(not(feature = "shellexecute-open"))]
+    let result = run_with_command(path, &app, options);
+
+    result.map_err(|err| app_error(err, path, &app))
+}
+
+/// Adds the path and the requested app to `err`, returned by opening `path` with `app`,
+/// rather than only naming the shell command running it.
+///
+/// If `app` isn't installed, the error is of kind [`ErrorKind::NOT_FOUND`].
+fn app_error(err: Error, path: &OsStr, app: &OsStr) -> Error {
+    let context = format!(
+        "failed to open '{}' with '{}'",
+        path.to_string_lossy(),
+        app.to_string_lossy()
+    );
+    if is_app_missing(&err, app) {
+        err.context(format!("{context}: app not found"))
+            .with_kind(ErrorKind::NOT_FOUND)
+    } else {
+        err.context(context)
+    }
+}
+
+/// Returns `true` if `err`, returned by launching `app`, is due to `app` not being installed.
+///
+/// Only launch failures are checked, so errors such as a veto of the pre-open hook are kept.
+fn is_app_missing(err: &Error, app: &OsStr) -> bool {
+    match err.kind() {
+        ErrorKind::IO if err.io_kind() == Some(std::io::ErrorKind::NotFound) => true,
+        ErrorKind::IO | ErrorKind::COMMAND_FAILED => !app::is_available(app),
+        _ => false,
+    }
+}
+
+/// Runs the command opening `path` with `app`, retrying if the detected shell went missing.
+fn run_with_command(path: &OsStr, app: &OsStr, options: &OpenOptions) -> Result<ExitStatus> {
+    let mut cmd = with_command_with_options(path, app, options);
+    match cmd.status_with_stdio(options.get_stdio()) {
+        Err(err) if redetect_missing_shell(&cmd, &err, options) => {
+            run_with_command(path, app, options)
+        }
+        status => checked_status(status, cmd),
+    }
+}
+
+/// Open path with an application bundled alongside the current executable.
+///
+/// `app` is resolved relative to the directory of [`std::env::current_exe()`],
+/// so it's found regardless of the current working directory. This is common for
+/// portable apps shipping helper tools next to their own executable.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// win_open::with_bundled("notes.txt", r"tools\editor.exe")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] of kind [`ErrorKind::NOT_FOUND`] is returned if the resolved app doesn't exist,
+/// otherwise a [`Error`] is returned on failure.
+pub fn with_bundled(path: impl AsRef<OsStr>, app: impl AsRef<std::path::Path>) -> Result<()> {
+    let app = app::resolve_bundled(app.as_ref())?;
+    with(path, app)
+}
+
+/// Launch `app` and write `data` to its stdin, e.g. to pipe content into a viewer.
+///
+/// Unlike [`with()`], `app` is launched directly rather than through a shell,
+/// so it must be an executable. Its stdin is closed once all data is written,
+/// without waiting for `app` to exit.
+///
+/// # Beware
+///
+/// This function blocks until all of `data` is written to the pipe. If `data` is larger
+/// than the pipe's buffer, that's only once `app` has read everything but the last buffer.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// win_open::with_stdin("Hello, world!", "more")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Like every launch, `app` goes through the pre-open hook and the mock runner.
+///
+/// ```
+/// # #[cfg(feature = "mock")]
+/// # {
+/// use std::{cell::RefCell, rc::Rc};
+/// use win_open::mock;
+///
+/// let programs = Rc::new(RefCell::new(Vec::new()));
+/// let recorded = Rc::clone(&programs);
+/// mock::set_runner(move |cmd| {
+///     recorded.borrow_mut().push(cmd.get_program().to_os_string());
+///     Ok(mock::exit_status(0))
+/// });
+/// let result = win_open::with_stdin("Hello, world!", "findstr");
+/// mock::clear_runner();
+///
+/// assert!(result.is_ok());
+/// assert_eq!(*programs.borrow(), ["findstr"]);
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] is returned if `app` can't be launched or `data` can't be written.
+pub fn with_stdin(data: impl AsRef<[u8]>, app: impl AppArg) -> Result<()> {
+    let mut cmd = Command::new(app.into_app());
+    cmd.spawn_with_stdin(data.as_ref())?;
+    Ok(())
+}
+
+/// Open path with the given application, falling back to the default application
+/// if `app` could not be found.
+///
+/// This is useful for optional, preferred applications. Only a failure to find
+/// `app` falls back to [`that()`]; if `app` was launched but failed, its error is returned.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// win_open::with_or_default("notes.txt", "notepad++")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// An app that was found, but failed, doesn't fall back to the default application.
+///
+/// ```
+/// # #[cfg(feature = "mock")]
+/// # {
+/// use std::{cell::Cell, rc::Rc};
+/// use win_open::{mock, ErrorKind, WindowsShell};
+///
+/// let runs = Rc::new(Cell::new(0));
+/// let counted = Rc::clone(&runs);
+/// mock::set_runner(move |_| {
+///     counted.set(counted.get() + 1);
+///     Ok(mock::exit_status(1))
+/// });
+/// let result = win_open::with_forced_shell(WindowsShell::Cmd, || {
+///     win_open::with_or_default("notes.txt", "notepad")
+/// });
+/// mock::clear_runner();
+///
+/// assert_eq!(result.unwrap_err().kind(), &ErrorKind::COMMAND_FAILED);
+/// assert_eq!(runs.get(), 1);
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] is returned if `app` failed, or if it could not be found and
+/// opening with the default application failed as well.
+pub fn with_or_default(path: impl AsRef<OsStr>, app: impl AppArg) -> Result<()> {
+    match with(path.as_ref(), app) {
+        Err(err) if err.is_not_found() => that(path),
+        result => result,
+    }
+}
+
+/// Open path with the first of the given applications that could be found, in order of preference.
+///
+/// This is useful for opening in the user's preferred app if installed, e.g. an editor.
+/// Only a failure to find an app advances to the next one; if an app was launched but failed,
+/// its error is returned. End the list with an app that is always present, such as `notepad`,
+/// to guarantee a fallback.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// win_open::with_any("notes.txt", ["notepad++", "code", "notepad"])?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Simulate only some apps being installed, falling back through a preference list.
+///
+/// ```
+/// # #[cfg(feature = "mock")]
+/// # {
+/// use win_open::mock;
+///
+/// // Only launches of `notepad` succeed, as if the other apps were missing.
+/// mock::set_runner(|cmd| {
+///     let is_notepad = cmd.get_args().any(|arg| arg == "\"notepad\"");
+///     Ok(mock::exit_status(if is_notepad { 0 } else { 1 }))
+/// });
+/// let result = win_open::with_any("notes.txt", ["missing-editor", "missing-ide", "notepad"]);
+/// mock::clear_runner();
+///
+/// assert!(result.is_ok());
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] is returned if an app failed. If none of the apps could be found,
+/// the [`Error`] of kind [`ErrorKind::NOT_FOUND`] of the last one is returned.
+pub fn with_any<I, T>(path: impl AsRef<OsStr>, apps: I) -> Result<()>
+where
+    I: IntoIterator<Item = T>,
+    T: AppArg,
+{
+    let mut last_err = None;
+    for app in apps {
+        match with(path.as_ref(), app) {
+            Err(err) if err.is_not_found() => last_err = Some(err),
+            result => return result,
+        }
+    }
+    Err(last_err.unwrap_or_else(|| Error::new(ErrorKind::NO_LAUNCHER, "")))
+}
+
+/// Check whether `app` is installed, e.g. before offering to open a path with it.
+///
+/// The app is searched like the shell would, in the current directory and in `PATH`,
+/// trying each extension of `PATHEXT` if it has none. Apps that aren't in `PATH`, such as
+/// `firefox`, are found if they're registered in the `App Paths` key of the registry.
+///
+/// Unlike [`WindowsShell::is_available()`], which runs a shell to check that it works,
+/// the app isn't launched.
+///
+/// # Examples
+///
+/// ```
+/// assert!(win_open::app_available("notepad"));
+/// assert!(!win_open::app_available("surely-not-an-installed-app"));
+/// ```
+///
+/// # Returns
+/// `true` if the app was found, `false` otherwise.
+pub fn app_available(app: impl AsRef<OsStr>) -> bool {
+    app::is_available(app.as_ref())
+}
+
+/// Open path with `app`, passing it at the position of the `{}` placeholder in `args_template`.
+///
+/// This supports apps that expect the path in a specific argument, rather than at the end.
+/// Every `{}` in the arguments is replaced by the path, and each argument is quoted.
+/// If no argument contains the placeholder, the path is appended at the end, like [`with()`].
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// win_open::with_template("notes.txt", "mytool", ["--input", "{}", "--verbose"])?;
 /// # Ok(())
 /// # }
 /// ```
-pub fn commands<T: AsRef<OsStr>>(path: T) -> Vec<Command> {
-    let shell = detect_shell().as_str();
-    let mut cmd = Command::new(shell);
-    match shell {
-        "pwsh" => cmd
-            .arg("-NoProfile")
-            .arg("-Command")
-            .arg("Start-Process")
-            .arg(wrap_in_quotes(path.as_ref()))
-            .creation_flags(CREATE_NO_WINDOW),
-        "nu" => cmd
-            .arg("-c")
-            .arg(format!("open {}", wrap_in_quotes_string(path.as_ref())))
-            .creation_flags(CREATE_NO_WINDOW),
-        "cmd" => cmd
-            .arg("/c")
-            .arg("start")
-            .raw_arg("\"\"")
-            .raw_arg(wrap_in_quotes(path))
-            .creation_flags(CREATE_NO_WINDOW),
-        _ => panic!("No supported shell detected."),
-    };
-    vec![cmd]
+///
+/// Pass the path at the position of a placeholder, or at the end if there's none.
+///
+/// ```
+/// # #[cfg(feature = "mock")]
+/// # {
+/// use std::{cell::RefCell, rc::Rc};
+/// use win_open::{mock, WindowsShell};
+///
+/// let args = Rc::new(RefCell::new(Vec::new()));
+/// let recorded = Rc::clone(&args);
+/// mock::set_runner(move |cmd| {
+///     let cmd_args: Vec<_> = cmd.get_args().map(|arg| arg.to_os_string()).collect();
+///     recorded.borrow_mut().push(cmd_args);
+///     Ok(mock::exit_status(0))
+/// });
+/// win_open::with_forced_shell(WindowsShell::Cmd, || {
+///     win_open::with_template("notes.txt", "mytool", ["--input", "{}", "--verbose"]).unwrap();
+///     win_open::with_template("notes.txt", "mytool", ["--verbose"]).unwrap();
+/// });
+/// mock::clear_runner();
+///
+/// let args = args.borrow();
+/// assert_eq!(args[0][3..], [r#""mytool""#, r#""--input""#, r#""notes.txt""#, r#""--verbose""#]);
+/// assert_eq!(args[1][3..], [r#""mytool""#, r#""--verbose""#, r#""notes.txt""#]);
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Like [`with()`], a [`Error`] of kind [`ErrorKind::NOT_FOUND`] is returned if `app`
+/// isn't installed.
+///
+/// ```
+/// # #[cfg(feature = "mock")]
+/// # {
+/// use win_open::{mock, ErrorKind, WindowsShell};
+///
+/// mock::set_runner(|_| Err(std::io::ErrorKind::NotFound.into()));
+/// let result = win_open::with_forced_shell(WindowsShell::Cmd, || {
+///     win_open::with_template("notes.txt", "mytool", ["--input", "{}"])
+/// });
+/// mock::clear_runner();
+///
+/// assert_eq!(result.unwrap_err().kind(), &ErrorKind::NOT_FOUND);
+/// # }
+/// ```
+///
+/// Otherwise a [`Error`] is returned on failure.
+pub fn with_template<I, T>(
+    path: impl AsRef<OsStr>,
+    app: impl AppArg,
+    args_template: I,
+) -> Result<()>
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<str>,
+{
+    let path = &*target::strip_surrounding(path.as_ref());
+    let app = app.into_app();
+    let options = OpenOptions::new();
+    check_with_target(path, &app, &options)?;
+
+    let args = template_args(path, args_template);
+    args.iter().try_for_each(|arg| target::check_nul(arg))?;
+    let mut cmd = with_args_command_with_options(&app, &args, &options);
+    cmd.status_without_output()
+        .into_result(cmd)
+        .map_err(|err| app_error(err, path, &app))
+}
+
+/// Replaces the `{}` placeholders in `args_template` with `path`, or appends it if there are none.
+fn template_args<I, T>(path: &OsStr, args_template: I) -> Vec<OsString>
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<str>,
+{
+    const PLACEHOLDER: &str = "{}";
+
+    let mut has_placeholder = false;
+    let mut args: Vec<OsString> = args_template
+        .into_iter()
+        .map(|arg| {
+            let arg = arg.as_ref();
+            if arg == PLACEHOLDER {
+                // Keep the path exactly as given, without a lossy conversion.
+                has_placeholder = true;
+                path.to_os_string()
+            } else if arg.contains(PLACEHOLDER) {
+                has_placeholder = true;
+                arg.replace(PLACEHOLDER, &path.to_string_lossy()).into()
+            } else {
+                arg.into()
+            }
+        })
+        .collect();
+
+    if !has_placeholder {
+        args.push(path.to_os_string());
+    }
+    args
+}
+
+/// Open every file matching a wildcard pattern with the default application.
+///
+/// The wildcards `*` and `?` are expanded in the file name of `pattern`, e.g. `C:\logs\*.txt`,
+/// as shells don't expand them when opening paths. Matching is case-insensitive.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let opened = win_open::that_glob(r"C:\logs\*.txt")?;
+/// println!("Opened {} files.", opened);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Returns
+/// The number of files opened.
+///
+/// # Errors
+///
+/// A [`Error`] of kind [`ErrorKind::NOT_FOUND`] is returned if no file matched,
+/// otherwise a [`Error`] is returned if opening any file failed.
+pub fn that_glob(pattern: impl AsRef<std::path::Path>) -> Result<usize> {
+    let paths = glob::expand(pattern.as_ref())?;
+    for path in &paths {
+        that(path)?;
+    }
+    Ok(paths.len())
+}
+
+/// Open path with the default application, capturing the launcher's output for diagnostics.
+///
+/// This behaves like [`that()`], but if a launcher fails, the text it wrote to
+/// stderr is included in the message of the returned error.
+///
+/// # Examples
+///
+/// ```no_run
+/// let path = "http://rust-lang.org";
+///
+/// if let Err(err) = win_open::that_captured(path) {
+///     eprintln!("An error occurred when opening '{}': {}", path, err);
+/// }
+/// ```
+///
+/// The path is checked like by [`that()`], so device paths are rejected without running anything.
+///
+/// ```
+/// use win_open::ErrorKind;
+///
+/// let err = win_open::that_captured(r"\\.\PhysicalDrive0").unwrap_err();
+/// assert_eq!(err.kind(), &ErrorKind::INVALID_INPUT);
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] is returned on failure, including the launcher's stderr if it ran but failed.
+pub fn that_captured(path: impl AsRef<OsStr>) -> Result<()> {
+    let options = OpenOptions::new();
+    let path = checked_target(path.as_ref(), &options)?;
+
+    let mut last_err = None;
+    for mut cmd in commands_with_options(path, &options) {
+        match cmd.output_without_input().into_result(cmd) {
+            Ok(()) => return Ok(()),
+            Err(err) => last_err = Some(err),
+        }
+    }
+    Err(last_err.unwrap_or_else(|| Error::new(ErrorKind::NO_LAUNCHER, "")))
+}
+
+/// Open path with the given application, capturing the launcher's output for diagnostics.
+///
+/// This behaves like [`with()`], but if the launcher fails, the text it wrote to
+/// stderr is included in the message of the returned error.
+///
+/// # Errors
+///
+/// A [`Error`] is returned on failure, including the launcher's stderr if it ran but failed.
+pub fn with_captured(path: impl AsRef<OsStr>, app: impl AppArg) -> Result<()> {
+    let options = OpenOptions::new();
+    let path = &*target::strip_surrounding(path.as_ref());
+    let app = app.into_app();
+    check_with_target(path, &app, &options)?;
+
+    let mut cmd = with_command_with_options(path, app, &options);
+    cmd.output_without_input().into_result(cmd)
+}
+
+/// Open path with the default application for files of type `extension`.
+///
+/// This is useful for files without (or with a misleading) extension whose type is known,
+/// such as a downloaded PDF. The extension may be given with or without the leading dot.
+///
+/// With the `shellexecute` feature, the type is passed to `ShellExecuteExW` as the file class,
+/// unless a shell is chosen explicitly. Otherwise, `path` is copied to a new temporary file with the given extension, which is then
+/// opened. The copy is left in the temporary directory, as the app may still be reading it.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// win_open::that_as("downloads/invoice", "pdf")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// With a shell, the copy is opened instead.
+///
+/// ```
+/// # #[cfg(feature = "mock")]
+/// # {
+/// use std::{cell::RefCell, rc::Rc};
+/// use win_open::{mock, WindowsShell};
+///
+/// let invoice = std::env::temp_dir().join("win-open-invoice");
+/// std::fs::write(&invoice, "%PDF-1.7").unwrap();
+///
+/// let args = Rc::new(RefCell::new(Vec::new()));
+/// let recorded = Rc::clone(&args);
+/// mock::set_runner(move |cmd| {
+///     recorded.borrow_mut().extend(cmd.get_args().map(|arg| arg.to_os_string()));
+///     Ok(mock::exit_status(0))
+/// });
+/// win_open::with_forced_shell(WindowsShell::Cmd, || win_open::that_as(&invoice, "pdf")).unwrap();
+/// mock::clear_runner();
+///
+/// let copy = args.borrow().last().unwrap().to_string_lossy().trim_matches('"').to_string();
+/// assert!(copy.ends_with(r"\win-open-invoice.pdf"));
+/// assert_eq!(std::fs::read_to_string(copy).unwrap(), "%PDF-1.7");
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] is returned on failure.
+pub fn that_as(path: impl AsRef<OsStr>, extension: impl AsRef<OsStr>) -> Result<()> {
+    let path = checked_target(path.as_ref(), &OpenOptions::new())?;
+    target::check_nul(extension.as_ref())?;
+    let mut class = OsString::from(".");
+    class.push(extension.as_ref().to_string_lossy().trim_start_matches('.'));
+
+    #[cfg(feature = "shellexecute-open")]
+    if uses_execute(&OpenOptions::new()) {
+        return ExecuteRequest::new(path).class(class).execute();
+    }
+
+    that(copy_as(&path, &class)?)
+}
+
+/// Copies `path` to a new temporary file with the extension `class`, keeping its name.
+///
+/// Each copy gets a directory of its own, so opening files of the same name at the same time
+/// doesn't overwrite one with the other. Copies are left in place, as the app may still read them.
+fn copy_as(path: &OsStr, class: &OsStr) -> Result<std::path::PathBuf> {
+    static COPIED: AtomicUsize = AtomicUsize::new(0);
+
+    let path = std::path::Path::new(path);
+    let mut name = path
+        .file_name()
+        .unwrap_or(OsStr::new("file"))
+        .to_os_string();
+    name.push(class);
+
+    let dir = std::env::temp_dir().join("win-open").join(format!(
+        "as-{}-{}",
+        std::process::id(),
+        COPIED.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&dir)?;
+    let temp = dir.join(name);
+    std::fs::copy(path, &temp)?;
+    Ok(temp)
+}
+
+/// Get multiple commands that open `path` with the default application.
+///
+/// Each command represents a launcher to try. The command for the detected shell
+/// comes first, followed by an `explorer.exe` based command as a universal fallback.
+///
+/// App URIs such as `ms-settings:display`, and shell folders such as `shell:RecycleBinFolder`
+/// or `shell:Downloads`, are passed verbatim to `explorer.exe` only.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let path = "http://rust-lang.org";
+/// assert!(win_open::commands(path)[0].status()?.success());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// ```
+/// let cmds = win_open::commands("ms-settings:display");
+/// assert_eq!(cmds.len(), 1);
+/// assert_eq!(cmds[0].get_program(), "explorer.exe");
+/// assert_eq!(cmds[0].get_args().collect::<Vec<_>>(), ["ms-settings:display"]);
+///
+/// let cmds = win_open::commands("shell:RecycleBinFolder");
+/// assert_eq!(cmds[0].get_program(), "explorer.exe");
+/// assert_eq!(cmds[0].get_args().collect::<Vec<_>>(), ["shell:RecycleBinFolder"]);
+///
+/// // Query parameters of deep links reach the handler unmodified.
+/// let uri = "slack://channel?team=T123&id=C456&message=a%20b";
+/// let cmds = win_open::commands(uri);
+/// assert_eq!(cmds.len(), 1);
+/// assert_eq!(cmds[0].get_args().collect::<Vec<_>>(), [uri]);
+/// ```
+///
+/// With the `shellexecute-open` feature, [`that()`] opens app URIs with the `open` verb
+/// of `ShellExecuteExW` instead, which also passes them verbatim.
+///
+/// Control Panel applets such as `ncpa.cpl`, and `control:` URIs with the canonical name of an item
+/// such as `control:printers`, are opened with `control.exe` only.
+///
+/// ```
+/// let cmds = win_open::commands("ncpa.cpl");
+/// assert_eq!(cmds[0].get_program(), "control.exe");
+/// assert_eq!(cmds[0].get_args().collect::<Vec<_>>(), ["ncpa.cpl"]);
+///
+/// let cmds = win_open::commands("control:printers");
+/// assert_eq!(cmds[0].get_program(), "control.exe");
+/// assert_eq!(cmds[0].get_args().collect::<Vec<_>>(), ["printers"]);
+/// ```
+///
+/// URLs with a fragment, such as `#section`, are opened with PowerShell if it's installed,
+/// before falling back to `cmd`, as `start` may not pass the fragment on. PowerShell passes
+/// them intact.
+///
+/// ```
+/// use win_open::WindowsShell;
+///
+/// let url = "https://example.com/page#section";
+/// let cmds = win_open::commands_with_shell(url, WindowsShell::Cmd);
+/// if cmds[0].get_program() == "pwsh.exe" {
+///     let args: Vec<_> = cmds[0].get_args().collect();
+///     assert_eq!(args[args.len() - 2..], ["-FilePath", "\"https://example.com/page#section\""]);
+/// }
+/// assert!(cmds.iter().any(|cmd| cmd.get_program() == "cmd.exe"));
+///
+/// let cmds = win_open::commands_with_shell(url, WindowsShell::Powershell);
+/// assert_eq!(cmds[0].get_args().last().unwrap(), "\"https://example.com/page#section\"");
+/// ```
+pub fn commands<T: AsRef<OsStr>>(path: T) -> Vec<Command> {
+    commands_with_options(path, &OpenOptions::new())
+}
+
+/// Get multiple commands that open `path` with the default application, using `shell`.
+///
+/// Unlike [`commands()`], no shell detection is performed, so no probe processes are spawned.
+/// This is useful in sandboxed environments where spawning processes may be blocked or logged.
+///
+/// # Examples
+///
+/// ```
+/// use win_open::WindowsShell;
+///
+/// let cmds = win_open::commands_with_shell("http://rust-lang.org", WindowsShell::Cmd);
+/// assert_eq!(cmds[0].get_program(), "cmd.exe");
+/// ```
+///
+/// With `cmd`, URLs and file paths are opened by the same command. `start` takes the first quoted
+/// token as the title of a new console window, which would leave a blank console open instead
+/// of opening a quoted URL, so an empty title always comes first.
+///
+/// ```
+/// use win_open::WindowsShell;
+///
+/// let cmd = win_open::commands_with_shell("https://rust-lang.org/?a=1&b=2", WindowsShell::Cmd);
+/// let args: Vec<_> = cmd[0].get_args().collect();
+/// assert_eq!(args, ["/c", "start", "\"\"", "\"https://rust-lang.org/?a=1&b=2\""]);
+///
+/// let cmd = win_open::commands_with_shell(r"C:\no such dir\notes.txt", WindowsShell::Cmd);
+/// let args: Vec<_> = cmd[0].get_args().collect();
+/// assert_eq!(args, ["/c", "start", "\"\"", r#""C:\no such dir\notes.txt""#]);
+/// ```
+///
+/// The path is escaped exactly like by [`with_command_with_shell()`], for every shell.
+///
+/// ```
+/// use win_open::WindowsShell;
+///
+/// let path = r#"C:\Users\me\R&D 100% "final"'s.txt"#;
+/// for shell in [WindowsShell::Powershell, WindowsShell::Nushell, WindowsShell::Cmd] {
+///     let that = win_open::commands_with_shell(path, shell).remove(0);
+///     let with = win_open::with_command_with_shell(path, "notepad", shell);
+///
+///     let that_args: Vec<_> = that.get_args().map(|arg| arg.to_string_lossy()).collect();
+///     let with_args: Vec<_> = with.get_args().map(|arg| arg.to_string_lossy()).collect();
+///     let quoted_path = that_args.last().unwrap().trim_start_matches("open ");
+///     assert!(with_args.iter().any(|arg| arg.ends_with(quoted_path)));
+/// }
+/// ```
+///
+/// Paths pasted with surrounding quotes, e.g. copied from Explorer, or surrounding whitespace
+/// are stripped, rather than quoted again. Whitespace is kept if the path exists as-is.
+///
+/// ```
+/// use win_open::WindowsShell;
+///
+/// for path in [r#""C:\no such dir\notes.txt""#, "  C:\\no such dir\\notes.txt\t"] {
+///     let cmd = win_open::commands_with_shell(path, WindowsShell::Cmd).remove(0);
+///     assert_eq!(cmd.get_args().last().unwrap(), r#""C:\no such dir\notes.txt""#);
+/// }
+/// ```
+pub fn commands_with_shell<T: AsRef<OsStr>>(path: T, shell: WindowsShell) -> Vec<Command> {
+    commands_with_options(path, OpenOptions::new().shell(shell))
+}
+
+fn commands_with_options<T: AsRef<OsStr>>(path: T, options: &OpenOptions) -> Vec<Command> {
+    let path = target::strip_surrounding(path.as_ref());
+
+    // Control Panel items aren't handled by `start`, only by `control.exe`.
+    if let Some(item) = target::control_panel_item(path.as_ref()) {
+        let mut cmd = Command::new("control.exe");
+        cmd.arg(item);
+        return vec![cmd];
+    }
+
+    // App URIs such as `ms-settings:display` and shell folders such as `shell:Downloads`
+    // are handed to `explorer.exe` as-is, as shells may treat them like paths.
+    if target::is_app_uri(path.as_ref()) {
+        return vec![explorer_command(&path)];
+    }
+
+    let shell = options.resolve_shell();
+    let cmd = shell_command(&path, None, shell, options);
+
+    // `start` may mangle or drop the fragment of URLs (`#section`), so they're handed to
+    // PowerShell if it's installed, which passes them intact.
+    if shell == WindowsShell::Cmd
+        && target::is_url(&path)
+        && has_fragment(&path)
+        && app::find_executable(OsStr::new(WindowsShell::Powershell.program())).is_some()
+    {
+        let mut url = Command::new(WindowsShell::Powershell.program());
+        url.args(options.profile_args())
+            .arg("-Command")
+            .arg("Start-Process")
+            .arg("-FilePath")
+            .arg(powershell_string_literal(&*path));
+        return vec![url, cmd, explorer_command(&path)];
+    }
+
+    vec![cmd, explorer_command(&path)]
+}
+
+/// Get a command that uses `app` to open `path`.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let path = "http://rust-lang.org";
+/// assert!(win_open::with_command(path, "app").status()?.success());
+/// # Ok(())
+/// # }
+/// ```
+pub fn with_command<T: AsRef<OsStr>>(path: T, app: impl AppArg) -> Command {
+    with_command_with_options(path, app, &OpenOptions::new())
+}
+
+/// Get a command that uses `app` to open `path`, using `shell`.
+///
+/// Unlike [`with_command()`], no shell detection is performed, so no probe processes are spawned.
+///
+/// # Examples
+///
+/// ```
+/// use win_open::WindowsShell;
+///
+/// let cmd = win_open::with_command_with_shell("notes.txt", "notepad", WindowsShell::Cmd);
+/// assert_eq!(cmd.get_program(), "cmd.exe");
+/// assert_eq!(
+///     cmd.get_args().collect::<Vec<_>>(),
+///     ["/c", "start", r#""""#, r#""notepad""#, r#""notes.txt""#]
+/// );
+/// ```
+///
+/// With PowerShell, URLs are passed in the argument list of the app, rather than positionally.
+///
+/// ```
+/// use win_open::WindowsShell;
+///
+/// let url = "https://rust-lang.org/?a=1&b=2";
+/// let cmd = win_open::with_command_with_shell(url, "firefox", WindowsShell::Powershell);
+/// let args: Vec<_> = cmd.get_args().collect();
+/// assert_eq!(
+///     args[args.len() - 4..],
+///     ["-FilePath", r#""firefox""#, "-ArgumentList", r#""https://rust-lang.org/?a=1&b=2""#]
+/// );
+/// ```
+///
+/// With Nushell, the app is run as an external command, rather than a Nushell command.
+///
+/// ```
+/// use win_open::WindowsShell;
+///
+/// let cmd = win_open::with_command_with_shell("notes.txt", "open", WindowsShell::Nushell);
+/// assert_eq!(cmd.get_args().collect::<Vec<_>>(), ["-c", "^'open' 'notes.txt'"]);
+/// ```
+pub fn with_command_with_shell<T: AsRef<OsStr>>(
+    path: T,
+    app: impl AppArg,
+    shell: WindowsShell,
+) -> Command {
+    with_command_for_shell(path, app, shell, &OpenOptions::new())
+}
+
+fn with_command_with_options<T: AsRef<OsStr>>(
+    path: T,
+    app: impl AppArg,
+    options: &OpenOptions,
+) -> Command {
+    with_command_for_shell(path, app, options.resolve_shell(), options)
+}
+
+fn with_command_for_shell<T: AsRef<OsStr>>(
+    path: T,
+    app: impl AppArg,
+    shell: WindowsShell,
+    options: &OpenOptions,
+) -> Command {
+    shell_command(path.as_ref(), Some(&app.into_app()), shell, options)
+}
+
+/// Builds the command opening `path` with `shell`, either with `app` or the default application.
+///
+/// This is shared by [`commands()`] and [`with_command()`], so both escape the path the same way.
+fn shell_command(
+    path: &OsStr,
+    app: Option<&OsStr>,
+    shell: WindowsShell,
+    options: &OpenOptions,
+) -> Command {
+    let path = &*target::strip_surrounding(path);
+    let mut cmd = Command::new(shell.program());
+
+    match (shell, app) {
+        (WindowsShell::Powershell, None) => cmd
+            .args(options.profile_args())
+            .arg("-Command")
+            .arg("Start-Process")
+            .arg(powershell_string_literal(path)),
+        // Invoke the app directly with the call operator, waiting for its exit code.
+        (WindowsShell::Powershell, Some(app)) if options.invokes_directly() => cmd
+            .args(options.profile_args())
+            .arg("-Command")
+            .arg("&")
+            .arg(powershell_string_literal(app))
+            .arg(powershell_string_literal(path)),
+        // URL handlers may misinterpret the URL when it's passed positionally,
+        // so name the program and its argument list explicitly.
+        (WindowsShell::Powershell, Some(app)) if target::is_url(path) => cmd
+            .args(options.profile_args())
+            .arg("-Command")
+            .arg("Start-Process")
+            .arg("-FilePath")
+            .arg(powershell_string_literal(app))
+            .arg("-ArgumentList")
+            .arg(powershell_string_literal(path)),
+        (WindowsShell::Powershell, Some(app)) => cmd
+            .args(options.profile_args())
+            .arg("-Command")
+            .arg("Start-Process")
+            .arg(powershell_string_literal(path))
+            .arg(powershell_string_literal(app)),
+        (WindowsShell::Nushell, None) => cmd
+            .arg("-c")
+            .arg(format!("open {}", nu_string_literal(path))),
+        // Run the app as an external command, with the path as its argument.
+        (WindowsShell::Nushell, Some(app)) => cmd.arg("-c").arg(format!(
+            "^{} {}",
+            nu_string_literal(app),
+            nu_string_literal(path)
+        )),
+        // `start` treats the first quoted token as the window title, so pass an
+        // empty title, followed by the app as the program and the path as its argument.
+        // Without it, a quoted URL would become the title of a blank console window.
+        (WindowsShell::Cmd, app) => {
+            cmd.arg("/c")
+                .args(options.codepage_args())
+                .arg("start")
+                .raw_arg("\"\"");
+            if let Some(app) = app {
+                cmd.raw_arg(wrap_in_quotes(app));
+            }
+            cmd.raw_arg(wrap_in_quotes(path))
+        }
+    };
+
+    cmd.creation_flags(CREATE_NO_WINDOW);
+    cmd
+}
+
+/// Get a command that launches `app` once, passing all of `args` as its arguments.
+fn with_args_command_with_options<T: AsRef<OsStr>>(
+    app: impl AsRef<OsStr>,
+    args: &[T],
+    options: &OpenOptions,
+) -> Command {
+    let shell = options.resolve_shell();
+    let mut cmd = Command::new(shell.program());
+
+    match shell {
+        WindowsShell::Powershell if options.invokes_directly() => {
+            cmd.args(options.profile_args())
+                .arg("-Command")
+                .arg("&")
+                .arg(powershell_string_literal(app));
+            for arg in args {
+                cmd.arg(powershell_string_literal(arg));
+            }
+            cmd.creation_flags(CREATE_NO_WINDOW)
+        }
+        WindowsShell::Powershell => {
+            let mut list = OsString::new();
+            for (i, arg) in args.iter().enumerate() {
+                if i > 0 {
+                    list.push(",");
+                }
+                list.push(powershell_string_literal(arg));
+            }
+            cmd.args(options.profile_args())
+                .arg("-Command")
+                .arg("Start-Process")
+                .arg("-FilePath")
+                .arg(powershell_string_literal(app))
+                .arg("-ArgumentList")
+                .arg(list)
+                .creation_flags(CREATE_NO_WINDOW)
+        }
+        WindowsShell::Nushell => {
+            let mut line = format!("^{}", nu_string_literal(app));
+            for arg in args {
+                line.push(' ');
+                line.push_str(&nu_string_literal(arg));
+            }
+            cmd.arg("-c").arg(line).creation_flags(CREATE_NO_WINDOW)
+        }
+        WindowsShell::Cmd => {
+            cmd.arg("/c")
+                .args(options.codepage_args())
+                .arg("start")
+                .raw_arg("\"\"")
+                .raw_arg(wrap_in_quotes(app));
+            for arg in args {
+                cmd.raw_arg(wrap_in_quotes(arg));
+            }
+            cmd.creation_flags(CREATE_NO_WINDOW)
+        }
+    };
+
+    cmd
+}
+
+/// Open all `paths` with a single invocation of `app`.
+///
+/// Most editors and viewers accept multiple files, so this avoids launching `app` once per path.
+/// Relative paths are made absolute. Nothing is run if `paths` is empty.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// win_open::with_all(["src/lib.rs", "src/main.rs"], "code")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] is returned on failure, e.g. if a path can't be resolved.
+pub fn with_all<I, T>(paths: I, app: impl AppArg) -> Result<()>
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<OsStr>,
+{
+    let options = OpenOptions::new();
+    let app = app.into_app();
+    target::check_nul(&app)?;
+    let paths = paths
+        .into_iter()
+        .map(|path| checked_target(path.as_ref(), &options))
+        .collect::<Result<Vec<_>>>()?;
+    if paths.is_empty() {
+        return Ok(());
+    }
+
+    let mut cmd = with_args_command_with_options(app, &paths, &options);
+    cmd.status_without_output().into_result(cmd)
+}
+
+/// Get a command that opens all `paths` with a single invocation of `app`, using `shell`.
+///
+/// This is the command run by [`with_all()`], except that `paths` are passed as-is.
+///
+/// # Examples
+///
+/// ```
+/// use win_open::WindowsShell;
+///
+/// let paths = ["a.txt", "b.txt"];
+/// let cmd = win_open::with_all_command_with_shell(paths, "code", WindowsShell::Cmd);
+/// assert_eq!(
+///     cmd.get_args().collect::<Vec<_>>(),
+///     ["/c", "start", r#""""#, r#""code""#, r#""a.txt""#, r#""b.txt""#]
+/// );
+///
+/// let cmd = win_open::with_all_command_with_shell(paths, "code", WindowsShell::Powershell);
+/// let args: Vec<_> = cmd.get_args().collect();
+/// assert_eq!(
+///     args[args.len() - 4..],
+///     ["-FilePath", r#""code""#, "-ArgumentList", r#""a.txt","b.txt""#]
+/// );
+///
+/// let cmd = win_open::with_all_command_with_shell(paths, "code", WindowsShell::Nushell);
+/// assert_eq!(cmd.get_args().collect::<Vec<_>>(), ["-c", "^'code' 'a.txt' 'b.txt'"]);
+/// ```
+pub fn with_all_command_with_shell<I, T>(paths: I, app: impl AppArg, shell: WindowsShell) -> Command
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<OsStr>,
+{
+    let paths: Vec<T> = paths.into_iter().collect();
+    with_args_command_with_options(app.into_app(), &paths, OpenOptions::new().shell(shell))
+}
+
+/// Open `target` as described by `spec`, the most general way to open a path.
+///
+/// `spec` bundles the shell, verb, app, arguments, window style and working directory,
+/// which are all optional. See [`OpenSpec`] for more details.
+///
+/// With the `shellexecute` feature, the target is opened with `ShellExecuteExW`,
+/// unless a shell is set explicitly in `spec`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use win_open::OpenSpec;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// win_open::open_with("report.pdf", OpenSpec::new().verb("print"))?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The target is checked like by [`that()`], e.g. rejecting device paths.
+///
+/// ```
+/// use win_open::{ErrorKind, OpenSpec};
+///
+/// let err = win_open::open_with(r"\\.\PhysicalDrive0", &OpenSpec::new()).unwrap_err();
+/// assert_eq!(err.kind(), &ErrorKind::INVALID_INPUT);
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] of kind [`ErrorKind::INVALID_INPUT`] is returned if `target` is rejected
+/// like by [`that()`], or if `spec` has a verb, window style or arguments the shell doesn't
+/// support. Otherwise a [`Error`] is returned on failure.
+pub fn open_with(target: impl AsRef<OsStr>, spec: &OpenSpec) -> Result<()> {
+    spec.check_nul()?;
+    let target = checked_target(target.as_ref(), spec.get_options())?;
+
+    #[cfg(feature = "shellexecute-open")]
+    if !spec.has_shell() && uses_execute(spec.get_options()) {
+        return open_with_execute(&target, spec);
+    }
+
+    let mut cmd = open_with_command(target, spec)?;
+    cmd.status_without_output().into_result(cmd)
+}
+
+/// Get a command that opens `target` as described by `spec`.
+///
+/// See documentation of [`open_with()`] for more details.
+///
+/// # Examples
+///
+/// ```
+/// use win_open::{ErrorKind, OpenSpec, WindowStyle, WindowsShell};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut spec = OpenSpec::new();
+/// spec.shell(WindowsShell::Cmd)
+///     .app("notepad")
+///     .show(WindowStyle::Maximized)
+///     .dir(r"C:\Temp");
+/// let cmd = win_open::open_with_command("notes.txt", &spec)?;
+/// assert_eq!(
+///     cmd.get_args().collect::<Vec<_>>(),
+///     ["/c", "start", r#""""#, "/D", r#""C:\Temp""#, "/MAX", r#""notepad""#, r#""notes.txt""#]
+/// );
+///
+/// let mut spec = OpenSpec::new();
+/// spec.shell(WindowsShell::Powershell).verb("print");
+/// let cmd = win_open::open_with_command("report.pdf", &spec)?;
+/// let args = cmd.get_args().collect::<Vec<_>>();
+/// assert!(args.ends_with(&[r#""report.pdf""#.as_ref(), "-Verb".as_ref(), r#""print""#.as_ref()]));
+///
+/// let mut spec = OpenSpec::new();
+/// spec.shell(WindowsShell::Cmd).verb("print");
+/// let err = win_open::open_with_command("report.pdf", &spec).unwrap_err();
+/// assert_eq!(err.kind(), &ErrorKind::INVALID_INPUT);
+///
+/// let mut spec = OpenSpec::new();
+/// spec.shell(WindowsShell::Nushell).show(WindowStyle::Minimized);
+/// let err = win_open::open_with_command("report.pdf", &spec).unwrap_err();
+/// assert_eq!(err.kind(), &ErrorKind::INVALID_INPUT);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The options of `spec` apply to the command, e.g. to switch `cmd` to the UTF-8 codepage.
+///
+/// ```
+/// use win_open::{OpenOptions, OpenSpec, WindowsShell};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut spec = OpenSpec::new();
+/// spec.shell(WindowsShell::Cmd)
+///     .options(OpenOptions::new().utf8_codepage(true).clone());
+/// let cmd = win_open::open_with_command("notes.txt", &spec)?;
+/// let args = cmd.get_args().collect::<Vec<_>>();
+/// assert_eq!(args[..3], ["/c", "chcp", "65001"]);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] of kind [`ErrorKind::INVALID_INPUT`] is returned if `spec` has a verb,
+/// window style or arguments the shell doesn't support:
+/// - Only PowerShell supports verbs.
+/// - Nushell doesn't support window styles, nor arguments without an app.
+/// - `cmd` doesn't support `WindowStyle::Hidden`.
+pub fn open_with_command<T: AsRef<OsStr>>(target: T, spec: &OpenSpec) -> Result<Command> {
+    let shell = spec.resolve_shell();
+    let unsupported = if spec.get_verb().is_some() && shell != WindowsShell::Powershell {
+        Some("verbs")
+    } else if spec.get_show().is_some() && shell == WindowsShell::Nushell {
+        Some("window styles")
+    } else if spec.get_show() == Some(WindowStyle::Hidden) && shell == WindowsShell::Cmd {
+        Some("hidden windows")
+    } else if !spec.get_args().is_empty()
+        && spec.get_app().is_none()
+        && shell == WindowsShell::Nushell
+    {
+        Some("arguments without an app")
+    } else {
+        None
+    };
+    if let Some(unsupported) = unsupported {
+        return Err(Error::new(
+            ErrorKind::INVALID_INPUT,
+            format!("{unsupported} are not supported by {}", shell.as_str()),
+        ));
+    }
+    target::check_nul(target.as_ref())?;
+    spec.check_nul()?;
+    let options = spec.get_options();
+
+    // The program to launch, followed by its arguments
+    let mut line: Vec<&OsStr> = Vec::new();
+    line.extend(spec.get_app().map(OsStr::new));
+    line.push(target.as_ref());
+    line.extend(spec.get_args().iter().map(OsString::as_os_str));
+
+    let mut cmd = Command::new(shell.program());
+    match shell {
+        WindowsShell::Powershell => {
+            cmd.args(options.profile_args())
+                .arg("-Command")
+                .arg("Start-Process")
+                .arg("-FilePath")
+                .arg(powershell_string_literal(line[0]));
+            if line.len() > 1 {
+                let mut list = OsString::new();
+                for (i, arg) in line[1..].iter().enumerate() {
+                    if i > 0 {
+                        list.push(",");
+                    }
+                    list.push(powershell_string_literal(arg));
+                }
+                cmd.arg("-ArgumentList").arg(list);
+            }
+            if let Some(verb) = spec.get_verb() {
+                cmd.arg("-Verb").arg(powershell_string_literal(verb));
+            }
+            if let Some(show) = spec.get_show() {
+                cmd.arg("-WindowStyle").arg(show.as_str());
+            }
+            if let Some(dir) = spec.get_dir() {
+                cmd.arg("-WorkingDirectory")
+                    .arg(powershell_string_literal(dir));
+            }
+        }
+        WindowsShell::Nushell => {
+            let mut script = String::new();
+            if let Some(dir) = spec.get_dir() {
+                script.push_str(&format!("cd {}; ", nu_string_literal(dir)));
+            }
+            if line.len() == 1 {
+                script.push_str(&format!("open {}", nu_string_literal(line[0])));
+            } else {
+                script.push('^');
+                script.push_str(
+                    &line
+                        .iter()
+                        .map(nu_string_literal)
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                );
+            }
+            cmd.arg("-c").arg(script);
+        }
+        WindowsShell::Cmd => {
+            cmd.arg("/c")
+                .args(options.codepage_args())
+                .arg("start")
+                .raw_arg("\"\"");
+            if let Some(dir) = spec.get_dir() {
+                cmd.arg("/D").raw_arg(wrap_in_quotes(dir));
+            }
+            match spec.get_show() {
+                Some(WindowStyle::Minimized) => cmd.arg("/MIN"),
+                Some(WindowStyle::Maximized) => cmd.arg("/MAX"),
+                _ => &mut cmd,
+            };
+            for arg in line {
+                cmd.raw_arg(wrap_in_quotes(arg));
+            }
+        }
+    };
+    cmd.creation_flags(CREATE_NO_WINDOW);
+
+    Ok(cmd)
+}
+
+/// Open path with the default application in the background, minimized without taking the focus.
+///
+/// This is useful for GUI apps opening helper windows, which shouldn't interrupt the user.
+/// With the `shellexecute-open` feature, the window is shown with `SW_SHOWMINNOACTIVE`
+/// and the shell shows no error dialogs. Otherwise, the window is started minimized,
+/// e.g. with `Start-Process -WindowStyle Minimized`, which may still activate it.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// win_open::that_background_noactivate("build-log.txt")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Opening in the background starts the window minimized.
+///
+/// ```
+/// # #[cfg(feature = "mock")]
+/// # {
+/// use std::{cell::RefCell, rc::Rc};
+/// use win_open::{mock, WindowsShell};
+///
+/// let args = Rc::new(RefCell::new(Vec::new()));
+/// let recorded = Rc::clone(&args);
+/// mock::set_runner(move |cmd| {
+///     recorded.borrow_mut().extend(cmd.get_args().map(|arg| arg.to_os_string()));
+///     Ok(mock::exit_status(0))
+/// });
+/// win_open::with_forced_shell(WindowsShell::Powershell, || {
+///     win_open::that_background_noactivate("build-log.txt").unwrap();
+/// });
+/// mock::clear_runner();
+///
+/// let args = args.borrow();
+/// let style = args.iter().position(|arg| arg == "-WindowStyle").unwrap();
+/// assert_eq!(args[style + 1], "Minimized");
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] is returned on failure.
+pub fn that_background_noactivate(path: impl AsRef<OsStr>) -> Result<()> {
+    let path = checked_target(path.as_ref(), &OpenOptions::new())?;
+
+    #[cfg(feature = "shellexecute-open")]
+    if uses_execute(&OpenOptions::new()) {
+        return ExecuteRequest::new(path)
+            .show(ffi::SW_SHOWMINNOACTIVE)
+            .no_ui()
+            .execute();
+    }
+
+    let mut cmd = open_with_command(path, OpenSpec::new().show(WindowStyle::Minimized))?;
+    cmd.status_without_output().into_result(cmd)
+}
+
+/// Open multiple URLs as tabs in a single browser window.
+///
+/// All URLs are passed to a single invocation of `browser`, which Chromium based
+/// browsers and Firefox open as tabs of one window. If `browser` is `None`,
+/// the family of the default browser is detected to find its executable.
+/// If the default browser is unknown, each URL is opened separately with [`that()`].
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let urls = ["https://rust-lang.org", "https://crates.io"];
+/// win_open::open_urls_as_tabs(urls, Some("firefox"))?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] is returned on failure.
+pub fn open_urls_as_tabs<I, T>(urls: I, browser: Option<&str>) -> Result<()>
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<OsStr>,
+{
+    let urls: Vec<T> = urls.into_iter().collect();
+    for url in &urls {
+        check_target(url.as_ref(), &OpenOptions::new())?;
+    }
+    let browser = match browser {
+        Some(browser) => browser,
+        None => match BrowserFamily::detect().executable() {
+            Some(browser) => browser,
+            None => return urls.iter().try_for_each(that),
+        },
+    };
+
+    let mut cmd = with_args_command_with_options(browser, &urls, &OpenOptions::new());
+    cmd.status_without_output().into_result(cmd)
+}
+
+/// Open a URL in the default browser, using `proxy`.
+///
+/// The family of the default browser is detected to pick its proxy flag
+/// (see [`BrowserFamily::proxy_flag()`]), e.g. `--proxy-server=` for Chromium based browsers.
+/// Note that a browser which is already running may keep using its current proxy.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// win_open::that_with_proxy("https://intranet.corp", "proxy.corp:8080")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] of kind [`ErrorKind::COMMAND_FAILED`] is returned if the default browser doesn't
+/// support a proxy flag, such as Firefox, which reads the proxy from its profile settings instead.
+/// Otherwise a [`Error`] is returned on failure.
+pub fn that_with_proxy(url: impl AsRef<OsStr>, proxy: &str) -> Result<()> {
+    check_target(url.as_ref(), &OpenOptions::new())?;
+    let family = BrowserFamily::detect();
+    if family == BrowserFamily::Firefox {
+        return Err(Error::new(
+            ErrorKind::COMMAND_FAILED,
+            "Firefox has no proxy flag, set the proxy in the settings of its profile instead",
+        ));
+    }
+    let (Some(browser), Some(flag)) = (family.executable(), family.proxy_flag(proxy)) else {
+        return Err(Error::new(
+            ErrorKind::COMMAND_FAILED,
+            format!("a proxy is not supported for the default browser ({family:?})"),
+        ));
+    };
+
+    let mut cmd = with_args_command_with_options(
+        browser,
+        &[OsStr::new(&flag), url.as_ref()],
+        &OpenOptions::new(),
+    );
+    cmd.status_without_output().into_result(cmd)
+}
+
+/// Open a URL with the default browser, and wait until the browser has started, for at most `timeout`.
+///
+/// [`that()`] returns once the launcher exits, while the browser may still be starting.
+/// This is useful to show a spinner until the browser is up.
+///
+/// This is a heuristic: the processes of the default browser are counted before opening the URL,
+/// and polled until a new one appears. Browsers start a process for each new window or tab,
+/// but a browser that reuses an existing process isn't detected. If the family of the default
+/// browser is unknown (see [`BrowserFamily::detect()`]), this returns right after opening.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// if !win_open::that_wait_ready("https://rust-lang.org", Duration::from_secs(10))? {
+///     println!("The browser is taking a while to start.");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Waiting for the browser to start returns within the timeout, even if it never appears.
+///
+/// ```
+/// # #[cfg(feature = "mock")]
+/// # {
+/// use std::time::{Duration, Instant};
+/// use win_open::mock;
+///
+/// mock::set_runner(|_| Ok(mock::exit_status(0)));
+/// let start = Instant::now();
+/// let ready = win_open::that_wait_ready("https://rust-lang.org", Duration::from_millis(500));
+/// mock::clear_runner();
+///
+/// assert!(!ready.unwrap());
+/// assert!(start.elapsed() < Duration::from_secs(5));
+/// # }
+/// ```
+///
+/// # Returns
+/// `true` if a new browser process appeared in time, `false` if it didn't or it couldn't be detected.
+/// Timing out isn't an error.
+///
+/// # Errors
+///
+/// A [`Error`] is returned if opening the URL failed.
+pub fn that_wait_ready(url: impl AsRef<OsStr>, timeout: Duration) -> Result<bool> {
+    const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+    let deadline = Instant::now() + timeout;
+    let executable = BrowserFamily::detect().executable();
+    let before = executable.and_then(browser::count_processes);
+    that(url)?;
+
+    let (Some(executable), Some(before)) = (executable, before) else {
+        return Ok(false);
+    };
+    loop {
+        if browser::count_processes(executable).is_some_and(|count| count > before) {
+            return Ok(true);
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Ok(false);
+        }
+        std::thread::sleep(POLL_INTERVAL.min(remaining));
+    }
+}
+
+/// Open the default email client to compose an email to `to`, with the given `subject` and `body`.
+///
+/// This builds a `mailto:` URI with [`mailto_uri()`], which is then opened like by [`that()`].
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// win_open::compose_email(&["support@example.com"], "Crash report", "Steps:\n1. Open the app")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] is returned on failure, e.g. if no email client is installed.
+pub fn compose_email(to: &[&str], subject: &str, body: &str) -> Result<()> {
+    that(mailto_uri(to, subject, body))
+}
+
+/// Build the `mailto:` URI opened by [`compose_email()`].
+///
+/// Multiple recipients are separated by commas. The subject and body are percent-encoded,
+/// with line breaks in the body normalized to `%0D%0A`, so they reach the email client intact.
+/// Empty fields are omitted.
+///
+/// # Examples
+///
+/// ```
+/// let uri = win_open::mailto_uri(
+///     &["a@example.com", "b+news@example.com"],
+///     "Q&A: 100% done?",
+///     "Hi,\nsee below.",
+/// );
+/// assert_eq!(
+///     uri,
+///     "mailto:a@example.com,b%2Bnews@example.com\
+///      ?subject=Q%26A%3A%20100%25%20done%3F&body=Hi%2C%0D%0Asee%20below."
+/// );
+///
+/// assert_eq!(win_open::mailto_uri(&["a@example.com"], "", ""), "mailto:a@example.com");
+/// ```
+pub fn mailto_uri(to: &[&str], subject: &str, body: &str) -> String {
+    target::mailto_uri(to, subject, body)
+}
+
+/// Open a URL in a private (incognito) window of the default browser.
+///
+/// The family of the default browser is detected to pick its private mode flag
+/// (see [`BrowserFamily::private_flag()`]).
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// win_open::that_private("https://rust-lang.org")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] of kind [`ErrorKind::COMMAND_FAILED`] is returned if private mode isn't supported
+/// for the default browser, rather than silently opening it in a normal window.
+/// Otherwise a [`Error`] is returned on failure.
+pub fn that_private(url: impl AsRef<OsStr>) -> Result<()> {
+    check_target(url.as_ref(), &OpenOptions::new())?;
+    let family = BrowserFamily::detect();
+    let (Some(browser), Some(flag)) = (family.executable(), family.private_flag()) else {
+        return Err(Error::new(
+            ErrorKind::COMMAND_FAILED,
+            format!("private mode is not supported for the default browser ({family:?})"),
+        ));
+    };
+
+    let mut cmd = with_args_command_with_options(
+        browser,
+        &[OsStr::new(flag), url.as_ref()],
+        &OpenOptions::new(),
+    );
+    cmd.status_without_output().into_result(cmd)
+}
+
+/// Open path with the default application and block until the launched application exits.
+///
+/// The mechanism depends on the detected shell: `start /wait` for `cmd` and `Start-Process -Wait`
+/// for PowerShell. Nushell has no way to wait for the opened application, so `cmd` is used instead.
+/// With the `shellexecute` feature, the process launched by `ShellExecuteExW` is waited on,
+/// as by `edit_and_wait()`, unless a shell is chosen explicitly.
+///
+/// # Beware
+///
+/// `/wait` and `-Wait` wait on the launched process. Applications handing the file off to an
+/// already running instance, as many editors and browsers do, make this return early.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// win_open::open_and_wait("report.pdf")?;
+/// println!("The report was closed");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// A shell chosen explicitly is used even with the `shellexecute` feature.
+///
+/// ```
+/// # #[cfg(feature = "mock")]
+/// # {
+/// use std::{cell::RefCell, rc::Rc};
+/// use win_open::{mock, WindowsShell};
+///
+/// let args = Rc::new(RefCell::new(Vec::new()));
+/// let recorded = Rc::clone(&args);
+/// mock::set_runner(move |cmd| {
+///     recorded.borrow_mut().extend(cmd.get_args().map(|arg| arg.to_os_string()));
+///     Ok(mock::exit_status(0))
+/// });
+/// win_open::with_forced_shell(WindowsShell::Cmd, || win_open::open_and_wait("report.pdf"))
+///     .unwrap();
+/// mock::clear_runner();
+///
+/// assert!(args.borrow().iter().any(|arg| arg == "/wait"));
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] is returned on failure.
+pub fn open_and_wait(path: impl AsRef<OsStr>) -> Result<()> {
+    let path = checked_target(path.as_ref(), &OpenOptions::new())?;
+
+    #[cfg(feature = "shellexecute-open")]
+    if uses_execute(&OpenOptions::new()) {
+        return edit_and_wait(path);
+    }
+
+    let mut cmd = open_and_wait_command(path, detect_shell());
+    cmd.status_without_output().into_result(cmd)
+}
+
+/// Get a command that opens `path` with the default application using `shell`,
+/// and waits until the launched application exits.
+///
+/// See documentation of [`open_and_wait()`] for more details.
+///
+/// # Examples
+///
+/// ```
+/// use win_open::WindowsShell;
+///
+/// let cmd = win_open::open_and_wait_command("report.pdf", WindowsShell::Cmd);
+/// assert!(cmd.get_args().any(|arg| arg == "/wait"));
+///
+/// let cmd = win_open::open_and_wait_command("report.pdf", WindowsShell::Powershell);
+/// assert!(cmd.get_args().any(|arg| arg == "-Wait"));
+/// ```
+pub fn open_and_wait_command<T: AsRef<OsStr>>(path: T, shell: WindowsShell) -> Command {
+    let options = OpenOptions::new();
+    match shell {
+        WindowsShell::Powershell => {
+            let mut cmd = Command::new(shell.program());
+            cmd.args(options.profile_args())
+                .arg("-Command")
+                .arg("Start-Process")
+                .arg("-Wait")
+                .arg(powershell_string_literal(path.as_ref()))
+                .creation_flags(CREATE_NO_WINDOW);
+            cmd
+        }
+        WindowsShell::Nushell | WindowsShell::Cmd => {
+            let mut cmd = Command::new(WindowsShell::Cmd.program());
+            cmd.arg("/c")
+                .arg("start")
+                .raw_arg("\"\"")
+                .arg("/wait")
+                .raw_arg(wrap_in_quotes(path.as_ref()))
+                .creation_flags(CREATE_NO_WINDOW);
+            cmd
+        }
+    }
+}
+
+/// Open a file at a specific line in the given editor.
+///
+/// The line jump syntax is known for VS Code (`code --goto path:line`),
+/// Sublime Text (`subl path:line`) and Notepad++ (`notepad++ -n<line> path`).
+/// Other editors open the file with [`with()`], ignoring the line.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// win_open::open_at_line("src/main.rs", 42, "code")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] is returned on failure.
+pub fn open_at_line(path: impl AsRef<OsStr>, line: u32, editor: impl AppArg) -> Result<()> {
+    let path = path.as_ref();
+    let editor = editor.into_app();
+    check_with_target(path, &editor, &OpenOptions::new())?;
+    let mut cmd = open_at_line_command(path, line, &*editor);
+    cmd.status_without_output()
+        .into_result(cmd)
+        .map_err(|err| app_error(err, path, &editor))
+}
+
+/// Get a command that opens `path` at `line` in the given editor.
+///
+/// See documentation of [`open_at_line()`] for the supported editors.
+pub fn open_at_line_command<T: AsRef<OsStr>>(path: T, line: u32, editor: impl AppArg) -> Command {
+    let editor = editor.into_app();
+    let name = std::path::Path::new(&editor)
+        .file_stem()
+        .map(|name| name.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+
+    let mut path_at_line = path.as_ref().to_os_string();
+    path_at_line.push(format!(":{line}"));
+
+    let args = match name.as_str() {
+        "code" | "code-insiders" => vec![OsString::from("--goto"), path_at_line],
+        "subl" | "sublime_text" => vec![path_at_line],
+        "notepad++" => vec![OsString::from(format!("-n{line}")), path.as_ref().into()],
+        _ => return with_command(path, editor),
+    };
+
+    with_args_command_with_options(editor, &args, &OpenOptions::new())
+}
+
+/// Open multiple paths with elevated privileges, showing a single UAC prompt.
+///
+/// A single elevated `cmd` is launched, which opens each path in turn, so the user
+/// only has to confirm elevation once rather than for every path. The elevated `cmd`
+/// starts in the system directory, so relative paths are made absolute first.
+/// Nothing is run, and no prompt shown, if `paths` is empty.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// win_open::open_all_elevated([r"C:\Windows\System32\drivers\etc\hosts", r"C:\Windows\win.ini"])?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] of kind [`ErrorKind::CANCELLED`] is returned if the user declined the UAC prompt,
+/// otherwise a [`Error`] is returned on failure, e.g. if a path can't be resolved.
+pub fn open_all_elevated<I, T>(paths: I) -> Result<()>
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<OsStr>,
+{
+    let options = OpenOptions::new();
+    let paths = paths
+        .into_iter()
+        .map(|path| checked_target(path.as_ref(), &options))
+        .collect::<Result<Vec<_>>>()?;
+    if paths.is_empty() {
+        return Ok(());
+    }
+
+    #[cfg(not(feature = "shellexecute-open"))]
+    {
+        let mut cmd = open_all_elevated_command(paths);
+        match cmd.status_without_output() {
+            Ok(status) if status.code() == Some(ERROR_CANCELLED) => {
+                Err(Error::new(ErrorKind::CANCELLED, "elevation was declined"))
+            }
+            status => status.into_result(cmd),
+        }
+    }
+
+    #[cfg(feature = "shellexecute-open")]
+    {
+        match ExecuteRequest::new("cmd.exe")
+            .verb("runas")
+            .parameters(elevated_script(&paths))
+            .execute()
+        {
+            Err(err) if err.io_code() == Some(ERROR_CANCELLED) => Err(err
+                .context("elevation was declined")
+                .with_kind(ErrorKind::CANCELLED)),
+            result => result,
+        }
+    }
+}
+
+/// Get a command that opens all `paths` with elevated privileges, behind a single UAC prompt.
+///
+/// This is the command run by [`open_all_elevated()`] without the `shellexecute-open` feature,
+/// except that `paths` are passed as-is. Only PowerShell can request elevation, so it's used
+/// to start the elevated `cmd`, even if it isn't the detected shell. The command exits with
+/// `ERROR_CANCELLED` (1223) if the user declines the UAC prompt.
+///
+/// # Examples
+///
+/// ```
+/// use win_open::WindowsShell;
+///
+/// let cmd = win_open::with_forced_shell(WindowsShell::Cmd, || {
+///     win_open::open_all_elevated_command([r"C:\a.txt", r"C:\b.txt"])
+/// });
+/// assert_eq!(cmd.get_program(), "powershell.exe");
+///
+/// let script = cmd.get_args().last().unwrap().to_string_lossy().into_owned();
+/// assert_eq!(script.matches("Start-Process").count(), 1);
+/// assert!(script.contains(r#"-ArgumentList '/c start "" "C:\a.txt" & start "" "C:\b.txt"'"#));
+/// ```
+pub fn open_all_elevated_command<I, T>(paths: I) -> Command
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<OsStr>,
+{
+    let paths: Vec<T> = paths.into_iter().collect();
+    let program = match detect_shell() {
+        WindowsShell::Powershell => WindowsShell::Powershell.program(),
+        _ => "powershell.exe",
+    };
+    let script = elevated_script(&paths)
+        .to_string_lossy()
+        .replace('\'', "''");
+
+    let mut cmd = Command::new(program);
+    cmd.arg("-NoProfile").arg("-Command").arg(format!(
+        "try {{ Start-Process -Verb RunAs -FilePath cmd.exe -ArgumentList '{script}' \
+         -ErrorAction Stop }} catch {{ \
+         if ($_.Exception.InnerException.NativeErrorCode -eq {ERROR_CANCELLED}) \
+         {{ exit {ERROR_CANCELLED} }}; throw }}"
+    ));
+    cmd.creation_flags(CREATE_NO_WINDOW);
+    cmd
+}
+
+/// The `ERROR_CANCELLED` error code, reported when the user declines a UAC prompt.
+const ERROR_CANCELLED: i32 = 1223;
+
+/// The arguments of the `cmd` opening each of `paths` in turn.
+fn elevated_script<T: AsRef<OsStr>>(paths: &[T]) -> OsString {
+    let mut script = OsString::from("/c");
+    for (i, path) in paths.iter().enumerate() {
+        script.push(if i > 0 {
+            " & start \"\" "
+        } else {
+            " start \"\" "
+        });
+        script.push(wrap_in_quotes(path));
+    }
+    script
+}
+
+/// Show the "Open with" dialog for `path`, letting the user pick the application.
+///
+/// This uses `rundll32`, so it works without the `shellexecute` feature.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// win_open::open_with_dialog("notes.txt")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] is returned on failure.
+pub fn open_with_dialog(path: impl AsRef<OsStr>) -> Result<()> {
+    check_target(path.as_ref(), &OpenOptions::new())?;
+    let mut cmd = open_with_dialog_command(path);
+    cmd.status_without_output().into_result(cmd)
+}
+
+/// Get a command that shows the "Open with" dialog for `path`.
+///
+/// # Examples
+///
+/// ```
+/// let cmd = win_open::open_with_dialog_command(r"C:\notes.txt");
+/// assert_eq!(cmd.get_program(), "rundll32.exe");
+/// ```
+pub fn open_with_dialog_command<T: AsRef<OsStr>>(path: T) -> Command {
+    rundll32_command("shell32.dll,OpenAs_RunDLL", path)
+}
+
+/// Returns `true` if the URL `url` has a fragment, such as `#section`.
+fn has_fragment(url: &OsStr) -> bool {
+    url.to_string_lossy().contains('#')
+}
+
+/// Get a command that invokes a shell action through `rundll32.exe`.
+///
+/// The `entry` point (e.g. `shell32.dll,OpenAs_RunDLL`) receives the remainder of the
+/// command line, so `arg` is passed verbatim rather than quoted.
+fn rundll32_command<T: AsRef<OsStr>>(entry: &str, arg: T) -> Command {
+    let mut cmd = Command::new("rundll32.exe");
+    cmd.arg(entry).raw_arg(arg);
+    cmd
+}
+
+/// Get a command that opens `path` through `explorer.exe`.
+///
+/// This works for files, folders and URLs on virtually every Windows install,
+/// without depending on any shell being available.
+fn explorer_command<T: AsRef<OsStr>>(path: T) -> Command {
+    let mut cmd = Command::new("explorer.exe");
+    if target::is_app_uri(path.as_ref()) {
+        cmd.arg(path.as_ref());
+    } else {
+        cmd.raw_arg(wrap_in_quotes(path));
+    }
+    cmd
+}
+
+/// Open path with the default application in a new thread to assure it's non-blocking.
+///
+/// See documentation of [`that()`] for more details.
+#[cfg(feature = "threads")]
+pub fn that_in_background(path: impl AsRef<OsStr>) -> std::thread::JoinHandle<Result<()>> {
+    let path = path.as_ref().to_os_string();
+    std::thread::spawn(|| that(path))
+}
+
+/// Open path with the default application in a new thread, returning a handle that can cancel it.
+///
+/// Unlike [`that_in_background()`], the open can be abandoned, e.g. when it's slow and the
+/// user closes the dialog that triggered it. See [`BackgroundOpen`] for more details.
+///
+/// See documentation of [`that()`] for more details.
+#[cfg(feature = "threads")]
+pub fn that_in_background_cancellable(path: impl AsRef<OsStr>) -> BackgroundOpen {
+    let path = path.as_ref().to_os_string();
+    BackgroundOpen::spawn(|| that(path))
+}
+
+/// Open path with the given application in a new thread, which is useful if
+/// the program ends up to be blocking. Otherwise, prefer [`with()`] for
+/// straightforward error handling.
+///
+/// See documentation of [`with()`] for more details.
+#[cfg(feature = "threads")]
+pub fn with_in_background<T: AsRef<OsStr>>(
+    path: T,
+    app: impl AppArg,
+) -> std::thread::JoinHandle<Result<()>> {
+    let path = path.as_ref().to_os_string();
+    let app = app.into_app();
+    std::thread::spawn(|| with(path, app))
+}
+
+/// Verify that the environment supports opening paths, e.g. for a diagnostics report.
+///
+/// The supported shell is detected and a harmless no-op command is run with it,
+/// surfacing environment problems before the user actually tries to open something.
+///
+/// # Examples
+///
+/// ```no_run
+/// match win_open::self_check() {
+///     Ok(shell) => println!("Ready to open paths using {}.", shell.as_str()),
+///     Err(err) => eprintln!("Opening paths won't work: {}", err),
+/// }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] is returned if the detected shell can't be run.
+pub fn self_check() -> Result<WindowsShell> {
+    let shell = detect_shell();
+    let mut cmd = Command::new(shell.program());
+    match shell {
+        WindowsShell::Powershell => cmd.arg("-NoProfile").arg("-Command").arg("exit 0"),
+        WindowsShell::Nushell => cmd.arg("-c").arg("exit 0"),
+        WindowsShell::Cmd => cmd.arg("/c").arg("exit 0"),
+    };
+    cmd.creation_flags(CREATE_NO_WINDOW);
+    cmd.status_without_output().into_result(cmd).map(|()| shell)
+}
+
+/// Run `f` with the detected shell forced to `shell` on the current thread, e.g. for reproducible tests.
+///
+/// Shell detection depends on the machine, so tests of the generated commands would be
+/// environment dependent. Within `f`, `shell` is used wherever the shell would be detected,
+/// and no probe processes are spawned. The override is thread-local, and is removed
+/// once `f` returns or panics.
+///
+/// # Examples
+///
+/// ```
+/// use win_open::WindowsShell;
+///
+/// for (shell, program) in [
+///     (WindowsShell::Powershell, "pwsh.exe"),
+///     (WindowsShell::Nushell, "nu.exe"),
+///     (WindowsShell::Cmd, "cmd.exe"),
+/// ] {
+///     let cmds = win_open::with_forced_shell(shell, || win_open::commands("notes.txt"));
+///     assert_eq!(cmds[0].get_program(), program);
+///     assert_eq!(cmds[1].get_program(), "explorer.exe");
+/// }
+/// ```
+pub fn with_forced_shell<R>(shell: WindowsShell, f: impl FnOnce() -> R) -> R {
+    /// Restores the previous override when dropped, including while unwinding.
+    struct Restore(Option<WindowsShell>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            FORCED_SHELL.with(|forced| forced.set(self.0));
+        }
+    }
+
+    let _restore = Restore(FORCED_SHELL.with(|forced| forced.replace(Some(shell))));
+    f()
+}
+
+/// The name of the environment variable selecting the shell, e.g. `WIN_OPEN_SHELL=cmd`.
+const SHELL_ENV_VAR: &str = "WIN_OPEN_SHELL";
+
+/// Set the shell used for the whole process, instead of detecting it, or `None` to detect it again.
+///
+/// The shell is chosen with the following precedence, from highest to lowest:
+/// 1. The thread-local shell forced by [`with_forced_shell()`].
+/// 2. The shell set with this function.
+/// 3. The shell named by the `WIN_OPEN_SHELL` environment variable (`pwsh`, `nu` or `cmd`).
+///    Unrecognized values are ignored.
+/// 4. The detected shell, which is cached after the first detection.
+///
+/// So an override takes effect even if a different shell was already detected and cached.
+/// Like [`set_default_app()`], this is global mutable state, affecting every caller in the process.
+///
+/// # Examples
+///
+/// ```
+/// use win_open::WindowsShell;
+///
+/// win_open::set_shell(Some(WindowsShell::Cmd));
+/// assert_eq!(win_open::commands("notes.txt")[0].get_program(), "cmd.exe");
+///
+/// win_open::set_shell(None);
+/// assert_eq!(win_open::shell_override(), None);
+/// ```
+///
+/// An explicit shell override wins, even after a different shell was detected and cached.
+///
+/// ```
+/// # #[cfg(feature = "mock")]
+/// # {
+/// use win_open::{mock, WindowsShell};
+///
+/// // Every probe succeeds, so PowerShell is detected and cached.
+/// mock::set_runner(|_| Ok(mock::exit_status(0)));
+/// assert_eq!(win_open::commands("notes.txt")[0].get_program(), "pwsh.exe");
+///
+/// win_open::set_shell(Some(WindowsShell::Cmd));
+/// assert_eq!(win_open::commands("notes.txt")[0].get_program(), "cmd.exe");
+///
+/// win_open::set_shell(None);
+/// assert_eq!(win_open::commands("notes.txt")[0].get_program(), "pwsh.exe");
+/// mock::clear_runner();
+/// # }
+/// ```
+///
+/// A detected shell that was uninstalled since is detected again, and the open retried.
+///
+/// ```
+/// # #[cfg(feature = "mock")]
+/// # {
+/// use std::{cell::Cell, io, rc::Rc};
+/// use win_open::mock;
+///
+/// let uninstalled = Rc::new(Cell::new(false));
+/// let is_uninstalled = Rc::clone(&uninstalled);
+/// mock::set_runner(move |cmd| {
+///     if is_uninstalled.get() && cmd.get_program() != "cmd.exe" {
+///         return Err(io::Error::from(io::ErrorKind::NotFound));
+///     }
+///     Ok(mock::exit_status(0))
+/// });
+/// assert_eq!(win_open::commands("notes.txt")[0].get_program(), "pwsh.exe");
+///
+/// uninstalled.set(true);
+/// win_open::with("notes.txt", "notepad").unwrap();
+/// assert_eq!(win_open::commands("notes.txt")[0].get_program(), "cmd.exe");
+/// mock::clear_runner();
+/// # }
+/// ```
+pub fn set_shell(shell: Option<WindowsShell>) {
+    *SHELL_OVERRIDE
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = shell;
+}
+
+/// Get the shell set with [`set_shell()`], if any.
+pub fn shell_override() -> Option<WindowsShell> {
+    *SHELL_OVERRIDE
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+/// The shell chosen explicitly, by [`with_forced_shell()`], [`set_shell()`] or the environment.
+fn explicit_shell() -> Option<WindowsShell> {
+    FORCED_SHELL
+        .with(Cell::get)
+        .or_else(shell_override)
+        .or_else(|| {
+            std::env::var(SHELL_ENV_VAR)
+                .ok()
+                .and_then(|shell| shell.trim().parse().ok())
+        })
+}
+
+fn detect_shell() -> WindowsShell {
+    if let Some(shell) = explicit_shell() {
+        return shell;
+    }
+
+    if let Some(shell) = *DETECTED_SHELL
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+    {
+        return shell;
+    }
+
+    // Detect without holding the lock, as probes and the timing hook may detect the shell
+    // themselves. Concurrent callers may probe too, but the first detected shell is kept.
+    let start = Instant::now();
+    let shell = get_shell();
+    report_timing(TimedStep::ShellDetection, start.elapsed());
+    let shell = match shell {
+        Ok(shell) => shell,
+        Err(err) => {
+            panic!("Failed to detect a supported shell: {}", err);
+        }
+    };
+
+    *DETECTED_SHELL
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get_or_insert(shell)
+}
+
+/// Detects the shell again if `err` reports that `cmd`, run in the detected shell, wasn't found.
+///
+/// The detected shell is cached, so it could have been uninstalled since. Shells chosen
+/// explicitly are used as-is.
+///
+/// # Returns
+/// `true` if a different shell was detected, so `cmd` should be rebuilt and run again.
+fn redetect_missing_shell(cmd: &Command, err: &std::io::Error, options: &OpenOptions) -> bool {
+    if err.kind() != std::io::ErrorKind::NotFound || options.explicit_shell().is_some() {
+        return false;
+    }
+
+    let missing = {
+        let mut detected = DETECTED_SHELL
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        match *detected {
+            Some(shell) if cmd.get_program() == shell.program() => detected.take(),
+            _ => None,
+        }
+    };
+    missing.is_some_and(|missing| detect_shell() != missing)
+}
+
+/// Set the timeout of each probe run during shell detection.
+///
+/// Detection runs `pwsh` and `nu` to check they work, which can hang if those
+/// are wedged, e.g. by a broken profile. A probe not finishing in time is killed,
+/// and its shell treated as unavailable. Defaults to 2 seconds.
+///
+/// This only has an effect if called before the shell is detected.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// win_open::set_probe_timeout(std::time::Duration::from_millis(500));
+/// win_open::that("http://rust-lang.org")?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn set_probe_timeout(timeout: Duration) {
+    PROBE_TIMEOUT_MS.store(timeout.as_millis() as u64, Ordering::Relaxed);
+}
+
+fn probe_timeout() -> Duration {
+    Duration::from_millis(PROBE_TIMEOUT_MS.load(Ordering::Relaxed))
+}
+
+/// Set the app used by [`that()`] for the whole process, or `None` to use the system default again.
+///
+/// This is useful when the user picks a preferred app once, e.g. a browser, which should
+/// then be used everywhere without passing it to [`with()`].
+///
+/// Note that this is global mutable state: it affects every caller of [`that()`] in the
+/// process, including other libraries, and changes made by one thread are seen by all others.
+///
+/// # Examples
+///
+/// ```
+/// win_open::set_default_app(Some("firefox".into()));
+/// assert_eq!(win_open::default_app().as_deref(), Some("firefox"));
+///
+/// win_open::set_default_app(None);
+/// assert_eq!(win_open::default_app(), None);
+/// ```
+pub fn set_default_app(app: Option<String>) {
+    *DEFAULT_APP.write().unwrap_or_else(PoisonError::into_inner) = app;
+}
+
+/// Get the app set with [`set_default_app()`], if any.
+pub fn default_app() -> Option<String> {
+    DEFAULT_APP
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Restrict the URL schemes opened by this crate for the whole process.
+///
+/// This is useful when opening user-provided links, which could otherwise trigger dangerous
+/// handlers through schemes such as `file:`, `javascript:` or arbitrary custom schemes.
+/// Schemes are compared case-insensitively, without the trailing `:`. Only targets with a scheme
+/// are checked, local paths such as `C:\notes.txt` are not affected. Every function opening
+/// a target checks it, including those passing it to an app, such as [`with()`], [`open_with()`],
+/// [`that_private()`] and [`OpenBatch`].
+///
+/// By default every scheme is allowed. Call [`clear_allowed_schemes()`] to allow them all again.
+///
+/// Note that this is global mutable state: it affects every caller of [`that()`] in the
+/// process, including other libraries, and changes made by one thread are seen by all others.
+///
+/// # Examples
+///
+/// ```
+/// use win_open::ErrorKind;
+///
+/// win_open::set_allowed_schemes(&["http", "https", "mailto"]);
+/// let err = win_open::that("file:///C:/Windows/System32/calc.exe").unwrap_err();
+/// assert_eq!(err.kind(), &ErrorKind::INVALID_INPUT);
+/// win_open::clear_allowed_schemes();
+/// ```
+///
+/// An allow-list of schemes permits `https` links, while `file:` URLs are rejected.
+///
+/// ```
+/// # #[cfg(feature = "mock")]
+/// # {
+/// use win_open::{mock, ErrorKind};
+///
+/// mock::set_runner(|_| Ok(mock::exit_status(0)));
+/// win_open::set_allowed_schemes(&["https"]);
+/// let allowed = win_open::that("https://rust-lang.org");
+/// let rejected = win_open::that("file:///C:/Windows/win.ini");
+/// win_open::clear_allowed_schemes();
+/// mock::clear_runner();
+///
+/// assert!(allowed.is_ok());
+/// assert_eq!(rejected.unwrap_err().kind(), &ErrorKind::INVALID_INPUT);
+/// # }
+/// ```
+///
+/// Disallowed schemes are rejected before anything is launched, whichever function opens them.
+///
+/// ```
+/// use win_open::{ErrorKind, OpenBatch, OpenSpec};
+///
+/// win_open::set_allowed_schemes(&["https"]);
+/// let with_spec = win_open::open_with("ms-settings:display", OpenSpec::new().app("notepad.exe"));
+/// let mut batch = OpenBatch::new();
+/// batch.add("ms-settings:display");
+/// let batched = batch.flush();
+/// win_open::clear_allowed_schemes();
+///
+/// assert_eq!(with_spec.unwrap_err().kind(), &ErrorKind::INVALID_INPUT);
+/// assert_eq!(batched.unwrap_err().kind(), &ErrorKind::INVALID_INPUT);
+/// ```
+pub fn set_allowed_schemes(schemes: &[&str]) {
+    let schemes = schemes
+        .iter()
+        .map(|scheme| scheme.trim_end_matches(':').to_ascii_lowercase())
+        .collect();
+    *ALLOWED_SCHEMES
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = Some(schemes);
+}
+
+/// Remove the restriction set with [`set_allowed_schemes()`], so every scheme is allowed again.
+pub fn clear_allowed_schemes() {
+    *ALLOWED_SCHEMES
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+/// Checks that the scheme of `path`, if any, is allowed by [`set_allowed_schemes()`].
+fn check_scheme(path: &OsStr) -> Result<()> {
+    let Some(scheme) = target::scheme(path) else {
+        return Ok(());
+    };
+    let allowed = ALLOWED_SCHEMES
+        .read()
+        .unwrap_or_else(PoisonError::into_inner);
+    match allowed.as_ref() {
+        Some(allowed) if !allowed.contains(&scheme) => Err(Error::new(
+            ErrorKind::INVALID_INPUT,
+            format!("the '{scheme}:' scheme is not allowed"),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Set a hook called with every command just before this crate spawns it, replacing any previous hook.
+///
+/// This allows embedders to log or veto opens centrally, e.g. for auditing or policy enforcement.
+/// Returning an error from the hook aborts spawning the command, and the error is returned by
+/// the function that tried to open the path. Note that functions falling back to other commands
+/// (such as [`that()`], which falls back to `explorer`) call the hook again for each of them.
+///
+/// Commands opened through `ShellExecuteExW` with the `shellexecute` feature aren't passed to the hook.
+/// Neither are the commands this crate runs to query the system rather than to open something,
+/// such as the shell detection probes and the registry queries for `App Paths` or the default browser.
+///
+/// # Examples
+///
+/// ```
+/// use win_open::{Error, ErrorKind, OpenOptions, WindowsShell};
+///
+/// win_open::set_pre_open_hook(|cmd| {
+///     if cmd.get_args().any(|arg| arg.to_string_lossy().contains("secret.txt")) {
+///         return Err(Error::new(ErrorKind::CANCELLED, "opening secrets is not allowed"));
+///     }
+///     Ok(())
+/// });
+///
+/// let err = OpenOptions::new().shell(WindowsShell::Cmd).that("secret.txt").unwrap_err();
+/// assert_eq!(err.kind(), &ErrorKind::CANCELLED);
+/// assert_eq!(err.message(), "opening secrets is not allowed");
+///
+/// win_open::clear_pre_open_hook();
+/// ```
+///
+/// Vetoing every command doesn't prevent detecting the shell.
+///
+/// ```
+/// # #[cfg(feature = "mock")]
+/// # {
+/// use win_open::{mock, Error, ErrorKind, WindowsShell};
+///
+/// win_open::set_pre_open_hook(|_| Err(Error::new(ErrorKind::CANCELLED, "no commands allowed")));
+/// mock::set_runner(|_| Ok(mock::exit_status(0)));
+/// let probed = WindowsShell::Cmd.probe();
+/// let opened = win_open::that("https://example.com");
+/// mock::clear_runner();
+/// win_open::clear_pre_open_hook();
+///
+/// assert!(probed.is_ok());
+/// assert_eq!(opened.unwrap_err().kind(), &ErrorKind::CANCELLED);
+/// # }
+/// ```
+pub fn set_pre_open_hook(hook: impl Fn(&Command) -> Result<()> + Send + Sync + 'static) {
+    *PRE_OPEN_HOOK
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = Some(Box::new(hook));
+}
+
+/// Remove the hook set with [`set_pre_open_hook()`], if any.
+pub fn clear_pre_open_hook() {
+    *PRE_OPEN_HOOK
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+/// A step of opening a path, whose duration is reported to the hook set with [`set_timing_hook()`].
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub enum TimedStep<'a> {
+    /// Detecting the supported shell, including running its probes. This happens once, unless
+    /// several threads detect it at the same time, or the detected shell goes missing.
+    ShellDetection,
+    /// Running a command, such as a launcher or a probe. For commands that are waited for, this
+    /// is the time until they exit, otherwise the time it took to spawn them.
+    Command(&'a Command),
+}
+
+/// Set a hook called with the duration of each step of opening a path, replacing any previous hook.
+///
+/// This quantifies the latency of launchers, e.g. to find out whether detecting the shell
+/// or running a command is slow. Commands run in turn are reported individually, such as
+/// the probes of shell detection, which is then reported as a whole.
+///
+/// Commands opened through `ShellExecuteExW` with the `shellexecute` feature aren't reported.
+///
+/// # Examples
+///
+/// ```no_run
+/// use win_open::TimedStep;
+///
+/// win_open::set_timing_hook(|step, duration| match step {
+///     TimedStep::ShellDetection => eprintln!("detecting the shell took {duration:?}"),
+///     TimedStep::Command(cmd) => eprintln!("{cmd:?} took {duration:?}"),
+///     _ => {}
+/// });
+/// win_open::that("http://rust-lang.org").unwrap();
+/// win_open::clear_timing_hook();
+/// ```
+///
+/// The duration of each command is reported to the timing hook.
+///
+/// ```
+/// # #[cfg(feature = "mock")]
+/// # {
+/// use std::sync::Mutex;
+/// use std::time::Duration;
+/// use win_open::{mock, TimedStep, WindowsShell};
+///
+/// static DURATIONS: Mutex<Vec<Duration>> = Mutex::new(Vec::new());
+/// win_open::set_timing_hook(|step, duration| {
+///     if let TimedStep::Command(_) = step {
+///         DURATIONS.lock().unwrap().push(duration);
+///     }
+/// });
+/// mock::set_runner(|_| {
+///     std::thread::sleep(Duration::from_millis(20));
+///     Ok(mock::exit_status(0))
+/// });
+/// win_open::with_forced_shell(WindowsShell::Cmd, || win_open::that("notes.txt")).unwrap();
+/// mock::clear_runner();
+/// win_open::clear_timing_hook();
+///
+/// let durations = DURATIONS.lock().unwrap();
+/// assert_eq!(durations.len(), 1);
+/// assert!(durations[0] >= Duration::from_millis(20));
+/// # }
+/// ```
+pub fn set_timing_hook(hook: impl Fn(TimedStep<'_>, Duration) + Send + Sync + 'static) {
+    *TIMING_HOOK.write().unwrap_or_else(PoisonError::into_inner) = Some(Box::new(hook));
+}
+
+/// Remove the hook set with [`set_timing_hook()`], if any.
+pub fn clear_timing_hook() {
+    *TIMING_HOOK.write().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+fn report_timing(step: TimedStep<'_>, duration: Duration) {
+    if let Some(hook) = &*TIMING_HOOK.read().unwrap_or_else(PoisonError::into_inner) {
+        hook(step, duration);
+    }
+}
+
+/// Runs `cmd` with `run`, reporting how long it took to the timing hook.
+fn timed<R>(cmd: &mut Command, run: impl FnOnce(&mut Command) -> R) -> R {
+    let start = Instant::now();
+    let result = run(cmd);
+    report_timing(TimedStep::Command(cmd), start.elapsed());
+    result
+}
+
+/// Runs the pre-open hook for `cmd`, if set.
+///
+/// An error returned by the hook is wrapped in an I/O error, and unwrapped again
+/// when converted back into an [`Error`].
+fn run_pre_open_hook(cmd: &Command) -> std::io::Result<()> {
+    match &*PRE_OPEN_HOOK.read().unwrap_or_else(PoisonError::into_inner) {
+        Some(hook) => hook(cmd).map_err(std::io::Error::other),
+        None => Ok(()),
+    }
+}
+
+fn get_shell() -> Result<WindowsShell> {
+    if WindowsShell::Powershell.is_available() {
+        return Ok(WindowsShell::Powershell);
+    }
+
+    if WindowsShell::Nushell.is_available() {
+        return Ok(WindowsShell::Nushell);
+    }
+
+    Ok(WindowsShell::Cmd)
+}
+
+/// Open path with the default application using a detached process. which is useful if
+/// the program ends up to be blocking or want to out-live your app
+///
+/// Unlike the commands returned by [`commands()`], which merely run without a window,
+/// the launcher is started as a `DETACHED_PROCESS` in its own process group. It doesn't
+/// inherit the console of your app and keeps running even if that console is closed.
+///
+/// See documentation of [`that()`] for more details.
+pub fn that_detached(path: impl AsRef<OsStr>) -> Result<()> {
+    that_detached_with_options(path, &OpenOptions::new())
+}
+
+fn that_detached_with_options(path: impl AsRef<OsStr>, options: &OpenOptions) -> Result<()> {
+    let path = checked_target(path.as_ref(), options)?;
+
+    // Only launchers spawned as commands can be assigned to a job.
+    #[cfg(feature = "shellexecute-open")]
+    if uses_execute(options) && !options.kills_on_exit() {
+        return that_detached_execute(path, options);
+    }
+
+    let mut last_err = None;
+    for mut cmd in commands_with_options(path, options) {
+        match cmd.spawn_detached() {
+            #[cfg(feature = "shellexecute-open")]
+            Ok(Some(child)) if options.kills_on_exit() => return job::kill_on_exit(&child),
+            Ok(_) => {
+                return Ok(());
+            }
+            Err(err) => last_err = Some(err),
+        }
+    }
+    Err(last_err.map_or_else(|| Error::new(ErrorKind::NO_LAUNCHER, ""), Error::from))
+}
+
+/// Open path with the given application using a detached process, which is useful if
+/// the program ends up to be blocking or want to out-live your app. Otherwise, prefer [`with()`] for
+/// straightforward error handling.
+///
+/// If the detected shell can't be spawned, `cmd` is tried instead.
+///
+/// See documentation of [`with()`] for more details.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "mock")]
+/// # {
+/// use std::{cell::RefCell, rc::Rc};
+/// use win_open::{mock, WindowsShell};
+///
+/// let programs = Rc::new(RefCell::new(Vec::new()));
+/// let recorded = Rc::clone(&programs);
+/// // Spawning PowerShell fails, as if it had been uninstalled since it was detected.
+/// mock::set_runner(move |cmd| {
+///     let program = cmd.get_program().to_os_string();
+///     recorded.borrow_mut().push(program.clone());
+///     if program == "pwsh.exe" {
+///         Err(std::io::ErrorKind::NotFound.into())
+///     } else {
+///         Ok(mock::exit_status(0))
+///     }
+/// });
+/// let result = win_open::with_forced_shell(WindowsShell::Powershell, || {
+///     win_open::with_detached("notes.txt", "notepad")
+/// });
+/// mock::clear_runner();
+///
+/// assert!(result.is_ok());
+/// assert_eq!(*programs.borrow(), ["pwsh.exe", "cmd.exe"]);
+/// # }
+/// ```
+pub fn with_detached<T: AsRef<OsStr>>(path: T, app: impl AppArg) -> Result<()> {
+    let path = target::strip_surrounding(path.as_ref());
+    let app = app.into_app();
+    check_with_target(&path, &app, &OpenOptions::new())?;
+
+    #[cfg(feature = "shellexecute-open")]
+    if uses_execute(&OpenOptions::new()) {
+        return with_detached_execute(path, app);
+    }
+
+    let mut last_err = None;
+
+    // Try the detected shell first, falling back to `cmd` if spawning it fails
+    let mut shells = vec![detect_shell()];
+    if shells[0] != WindowsShell::Cmd {
+        shells.push(WindowsShell::Cmd);
+    }
+
+    for shell in shells {
+        let mut cmd = with_command_for_shell(&*path, &app, shell, &OpenOptions::new());
+        match cmd.spawn_detached() {
+            Ok(_) => {
+                return Ok(()); // Successfully spawned the detached process
+            }
+            Err(err) => {
+                last_err = Some(err); // Store the error and try the next shell
+            }
+        }
+    }
+
+    Err(last_err.map_or_else(|| Error::new(ErrorKind::NO_LAUNCHER, ""), Error::from))
+}
+
+trait IntoResult<T> {
+    fn into_result(self, cmd: Command) -> T;
+}
+
+impl IntoResult<Result<()>> for std::io::Result<std::process::ExitStatus> {
+    fn into_result(self, cmd: Command) -> Result<()> {
+        checked_status(self, cmd).map(|_| ())
+    }
+}
+
+/// Turns the `result` of running `cmd` into an error if it failed or exited with a nonzero status.
+fn checked_status(result: std::io::Result<ExitStatus>, cmd: Command) -> Result<ExitStatus> {
+    match result {
+        Ok(status) if status.success() => Ok(status),
+        Ok(status) => Err(Error::new(
+            ErrorKind::COMMAND_FAILED,
+            format!("{cmd:?} ({})", status).as_str(),
+        )),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// The status reported when something was opened without running a launcher process.
+#[cfg(feature = "shellexecute-open")]
+fn launched() -> ExitStatus {
+    use std::os::windows::process::ExitStatusExt;
+    ExitStatus::from_raw(0)
+}
+
+impl IntoResult<Result<()>> for std::io::Result<std::process::Output> {
+    fn into_result(self, cmd: Command) -> Result<()> {
+        match self {
+            Ok(output) if output.status.success() => Ok(()),
+            Ok(output) => {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                Err(Error::new(
+                    ErrorKind::COMMAND_FAILED,
+                    format!("{cmd:?} ({}): {}", output.status, stderr.trim()).as_str(),
+                ))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// Extension methods applying this crate's standard launch settings to a [`Command`].
+///
+/// The commands returned by [`commands()`] and [`with_command()`] are owned and freely mutable,
+/// so their stdio and creation flags may be changed before spawning them. These methods
+/// re-apply the settings used by this crate's own functions.
+///
+/// # Examples
+///
+/// ```no_run
+/// use win_open::CommandExt;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// for mut cmd in win_open::commands("http://rust-lang.org") {
+///     if cmd.detached().spawn().is_ok() {
+///         break;
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub trait CommandExt {
+    /// Nulls stdin, stdout and stderr, as done by [`that()`] and [`with()`].
+    fn quiet(&mut self) -> &mut Self;
+
+    /// Nulls all stdio and starts the process as a `DETACHED_PROCESS` in a new process group,
+    /// as done by [`that_detached()`] and [`with_detached()`].
+    ///
+    /// The process doesn't inherit the console of the parent and keeps running
+    /// even if that console is closed.
+    fn detached(&mut self) -> &mut Self;
+}
+
+impl CommandExt for Command {
+    fn quiet(&mut self) -> &mut Self {
+        self.stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+    }
+
+    fn detached(&mut self) -> &mut Self {
+        // This is pretty much lifted from the implementation in Alacritty:
+        // https://github.com/alacritty/alacritty/blob/b9c886872d1202fc9302f68a0bedbb17daa35335/alacritty/src/daemon.rs
+        self.quiet().creation_flags(DETACHED_CREATION_FLAGS)
+    }
+}
+
+/// The creation flags of detached launchers, set by [`CommandExt::detached()`].
+///
+/// `DETACHED_PROCESS` (rather than `CREATE_NO_WINDOW`) means the launcher doesn't
+/// inherit the parent's console at all, so it fully out-lives the parent even if
+/// that console is closed. `CREATE_NO_WINDOW` would be ignored in combination with it.
+const DETACHED_CREATION_FLAGS: u32 = CREATE_NEW_PROCESS_GROUP | DETACHED_PROCESS;
+const CREATE_NEW_PROCESS_GROUP: u32 = 0x00000200;
+const DETACHED_PROCESS: u32 = 0x00000008;
+
+trait CommandRunExt {
+    fn status_without_output(&mut self) -> std::io::Result<std::process::ExitStatus>;
+    fn status_with_stdio(
+        &mut self,
+        stdio: StdioConfig,
+    ) -> std::io::Result<std::process::ExitStatus>;
+    fn output_without_input(&mut self) -> std::io::Result<std::process::Output>;
+    fn query_output(&mut self) -> std::io::Result<std::process::Output>;
+    fn status_with_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> std::io::Result<Option<std::process::ExitStatus>>;
+    fn spawn_detached(&mut self) -> std::io::Result<Option<std::process::Child>>;
+    fn spawn_with_stdin(&mut self, data: &[u8]) -> std::io::Result<()>;
+}
+
+impl CommandRunExt for Command {
+    fn status_without_output(&mut self) -> std::io::Result<std::process::ExitStatus> {
+        self.status_with_stdio(StdioConfig::default())
+    }
+
+    fn status_with_stdio(
+        &mut self,
+        stdio: StdioConfig,
+    ) -> std::io::Result<std::process::ExitStatus> {
+        stdio.apply(self);
+
+        run_pre_open_hook(self)?;
+
+        timed(self, |cmd| {
+            #[cfg(feature = "mock")]
+            if let Some(result) = mock::run(cmd) {
+                return result;
+            }
+
+            cmd.status()
+        })
+    }
+
+    fn output_without_input(&mut self) -> std::io::Result<std::process::Output> {
+        run_pre_open_hook(self)?;
+        self.query_output()
+    }
+
+    /// Runs a command querying the system, such as `reg.exe`, without passing it to the pre-open hook.
+    fn query_output(&mut self) -> std::io::Result<std::process::Output> {
+        self.stdin(Stdio::null()).creation_flags(CREATE_NO_WINDOW);
+
+        timed(self, |cmd| {
+            #[cfg(feature = "mock")]
+            if let Some(result) = mock::run(cmd) {
+                return result.map(|status| std::process::Output {
+                    status,
+                    stdout: Vec::new(),
+                    stderr: Vec::new(),
+                });
+            }
+
+            cmd.output()
+        })
+    }
+
+    fn status_with_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> std::io::Result<Option<std::process::ExitStatus>> {
+        self.quiet().creation_flags(CREATE_NO_WINDOW);
+
+        // Only shell probes are run with a timeout, and those aren't opens, so skip the hook.
+        timed(self, |cmd| {
+            #[cfg(feature = "mock")]
+            if let Some(result) = mock::run(cmd) {
+                return result.map(Some);
+            }
+
+            let mut child = cmd.spawn()?;
+            let start = Instant::now();
+            loop {
+                if let Some(status) = child.try_wait()? {
+                    return Ok(Some(status));
+                }
+                if start.elapsed() >= timeout {
+                    // The child may have exited in the meantime, so ignore errors.
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Ok(None);
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        })
+    }
+
+    /// Spawns the command detached, returning the child unless a mock runner handled it.
+    fn spawn_detached(&mut self) -> std::io::Result<Option<std::process::Child>> {
+        self.detached();
+
+        run_pre_open_hook(self)?;
+
+        timed(self, |cmd| {
+            #[cfg(feature = "mock")]
+            if let Some(result) = mock::run(cmd) {
+                return result.map(|_| None);
+            }
+
+            cmd.spawn().map(Some)
+        })
+    }
+
+    /// Spawns the command and writes `data` to its stdin, without waiting for it to exit.
+    fn spawn_with_stdin(&mut self, data: &[u8]) -> std::io::Result<()> {
+        use std::io::Write;
+
+        self.stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+
+        run_pre_open_hook(self)?;
+
+        timed(self, |cmd| {
+            #[cfg(feature = "mock")]
+            if let Some(result) = mock::run(cmd) {
+                return result.map(|_| ());
+            }
+
+            let mut child = cmd.spawn()?;
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin.write_all(data)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(feature = "shellexecute-open")]
+fn that_detached_execute<T: AsRef<OsStr>>(path: T, options: &OpenOptions) -> Result<()> {
+    let path =
+        target::extended_path(path.as_ref()).unwrap_or_else(|| normalize_path(path.as_ref()));
+    #[cfg(feature = "shellexecute-reveal")]
+    if std::fs::metadata(&path).is_ok_and(|f| f.is_dir()) {
+        return open_folder(path, true);
+    };
+
+    let request = ExecuteRequest::new(path)
+        .log_usage(options.logs_usage())
+        .hwnd(options.parent_hwnd());
+    match request.execute() {
+        // Some file types, such as `.ps1`, have no `open` verb but can still be edited.
+        Err(err) if options.falls_back_to_edit() && is_no_association(&err) => {
+            request.verb(EDIT_VERB).execute()
+        }
+        result => result,
+    }
+}
+
+/// The shell verb opening a file for editing.
+#[cfg(feature = "shellexecute-open")]
+const EDIT_VERB: &str = "edit";
+
+/// The `ERROR_NO_ASSOCIATION` error code, reported when a file has no association for a verb.
+#[cfg(feature = "shellexecute-open")]
+const ERROR_NO_ASSOCIATION: i32 = 1155;
+
+/// Returns `true` if `err` reports that there's no association for the requested verb.
+#[cfg(feature = "shellexecute-open")]
+fn is_no_association(err: &Error) -> bool {
+    err.is_no_association() || err.io_code() == Some(ERROR_NO_ASSOCIATION)
+}
+
+/// Open Explorer with the item at `path` selected in its parent folder.
+///
+/// This is useful to "reveal" a file, e.g. after downloading or exporting it.
+/// URLs and paths that don't exist can't be revealed.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// win_open::reveal(r"C:\Windows\win.ini")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] is returned on failure.
+#[cfg(feature = "shellexecute-reveal")]
+pub fn reveal<T: AsRef<OsStr>>(path: T) -> Result<()> {
+    target::check_nul(path.as_ref())?;
+    open_folder(normalize_path(path.as_ref()), false)
+}
+
+/// Get a command that opens Explorer with the item at `path` selected in its parent folder.
+///
+/// This is the command line counterpart of `reveal()`, to inspect or customize the invocation
+/// before running it. It doesn't need the `shellexecute-reveal` feature.
+/// Relative paths are made absolute.
+///
+/// # Examples
+///
+/// ```
+/// let cmd = win_open::reveal_command(r"C:\Windows\win.ini");
+/// assert_eq!(cmd.get_program(), "explorer.exe");
+/// assert_eq!(cmd.get_args().collect::<Vec<_>>(), [r#"/select,"C:\Windows\win.ini""#]);
+/// ```
+pub fn reveal_command<T: AsRef<OsStr>>(path: T) -> Command {
+    let path = path.as_ref();
+    let path = std::path::absolute(path).map_or_else(|_| path.to_os_string(), OsString::from);
+
+    // Explorer expects the path right after the comma, so it can't be a separate argument.
+    let mut arg = OsString::from("/select,");
+    arg.push(wrap_in_quotes(path));
+
+    let mut cmd = Command::new("explorer.exe");
+    cmd.raw_arg(arg);
+    cmd
+}
+
+/// The shell verb opening a folder in Explorer with its navigation tree expanded.
+#[cfg(feature = "shellexecute-open")]
+const EXPLORE_VERB: &str = "explore";
+
+/// Open the folder at `path` in Explorer, with the navigation tree expanded.
+///
+/// This differs from [`that()`], which uses the default `open` verb of folders.
+/// With the `shellexecute-open` feature, the `explore` verb is passed to `ShellExecuteExW`,
+/// otherwise [`explore_command()`] emulates it.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// win_open::explore(r"C:\Windows")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Exploring a folder runs `explorer /e,`, while other paths are rejected without running anything.
+///
+/// ```
+/// # #[cfg(feature = "mock")]
+/// # {
+/// use std::{cell::RefCell, rc::Rc};
+/// use win_open::{mock, ErrorKind};
+///
+/// let args = Rc::new(RefCell::new(Vec::new()));
+/// let recorded = Rc::clone(&args);
+/// mock::set_runner(move |cmd| {
+///     recorded.borrow_mut().extend(cmd.get_args().map(|arg| arg.to_os_string()));
+///     Ok(mock::exit_status(0))
+/// });
+/// let dir = std::env::temp_dir();
+/// win_open::explore(&dir).unwrap();
+/// let err = win_open::explore(dir.join("no-such-folder")).unwrap_err();
+/// mock::clear_runner();
+///
+/// assert_eq!(err.kind(), &ErrorKind::INVALID_INPUT);
+/// let expected = std::ffi::OsString::from(format!(r#"/e,"{}""#, dir.display()));
+/// assert_eq!(*args.borrow(), [expected]);
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// An error of kind [`ErrorKind::INVALID_INPUT`] is returned if `path` isn't a directory,
+/// or another [`Error`] on failure.
+pub fn explore<T: AsRef<OsStr>>(path: T) -> Result<()> {
+    let path = checked_target(path.as_ref(), &OpenOptions::new())?;
+    if !std::path::Path::new(&path).is_dir() {
+        return Err(Error::new(
+            ErrorKind::INVALID_INPUT,
+            format!("'{}' is not a directory", path.to_string_lossy()),
+        ));
+    }
+
+    #[cfg(feature = "shellexecute-open")]
+    if uses_execute(&OpenOptions::new()) {
+        return ExecuteRequest::new(path).verb(EXPLORE_VERB).execute();
+    }
+
+    let mut cmd = explore_command(path);
+    cmd.status_without_output().into_result(cmd)
+}
+
+/// Get a command that opens the folder at `path` in Explorer, with the navigation tree expanded.
+///
+/// This is the command line counterpart of `explore()`, emulating the `explore` verb with
+/// `explorer /e,`. Relative paths are made absolute.
+///
+/// # Examples
+///
+/// ```
+/// let cmd = win_open::explore_command(r"C:\Windows");
+/// assert_eq!(cmd.get_program(), "explorer.exe");
+/// assert_eq!(cmd.get_args().collect::<Vec<_>>(), [r#"/e,"C:\Windows""#]);
+/// ```
+pub fn explore_command<T: AsRef<OsStr>>(path: T) -> Command {
+    let path = path.as_ref();
+    let path = std::path::absolute(path).map_or_else(|_| path.to_os_string(), OsString::from);
+
+    // Like `/select,`, the path must follow the comma directly.
+    let mut arg = OsString::from("/e,");
+    arg.push(wrap_in_quotes(path));
+
+    let mut cmd = Command::new("explorer.exe");
+    cmd.raw_arg(arg);
+    cmd
+}
+
+/// Opens Explorer with `path` selected, or with `path` itself opened if `open` is `true`.
+#[cfg(feature = "shellexecute-reveal")]
+fn open_folder(path: OsString, open: bool) -> Result<()> {
+    let path = wide(path);
+    unsafe { ffi::CoInitialize(std::ptr::null()) };
+    let item = unsafe { ffi::ILCreateFromPathW(path.as_ptr()) };
+    if item.is_null() {
+        return Err(Error::new(
+            ErrorKind::NOT_FOUND,
+            from_wide(&path).to_string_lossy(),
+        ));
+    }
+
+    // With no children, the item itself is selected in its parent folder.
+    let children = [item as *const _];
+    let result = unsafe { SHOpenFolderAndSelectItems(item, open.then_some(&children[..]), 0) };
+    unsafe { ffi::ILFree(item) };
+    result
+}
+
+/// Open a new terminal in the directory `dir`, e.g. for an "Open terminal here" action.
+///
+/// Windows Terminal is used if it's available, as `wt.exe -d <dir>`. Otherwise, a new console
+/// window runs the detected shell in `dir`: `pwsh -NoExit`, `nu` or `cmd /k`.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// win_open::open_terminal(r"C:\Projects\win-open")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// A terminal is opened with Windows Terminal if it's available, or the detected shell otherwise.
+///
+/// ```
+/// # #[cfg(feature = "mock")]
+/// # {
+/// use std::{cell::RefCell, rc::Rc};
+/// use win_open::mock;
+///
+/// let commands = Rc::new(RefCell::new(Vec::new()));
+/// let recorded = Rc::clone(&commands);
+/// let dir = std::env::temp_dir();
+///
+/// // Windows Terminal is found in `App Paths`.
+/// mock::set_runner(|_| Ok(mock::exit_status(0)));
+/// let cmd = win_open::open_terminal_command(&dir).unwrap();
+/// assert_eq!(cmd.get_program(), "wt.exe");
+/// assert_eq!(cmd.get_args().collect::<Vec<_>>(), ["-d".as_ref(), dir.as_os_str()]);
+///
+/// // Windows Terminal is neither in `PATH` nor in `App Paths`, while PowerShell is installed.
+/// std::env::set_var("PATH", "");
+/// mock::set_runner(move |cmd| {
+///     let program = cmd.get_program().to_os_string();
+///     let args: Vec<_> = cmd.get_args().map(|arg| arg.to_os_string()).collect();
+///     recorded.borrow_mut().push((program, args));
+///     Ok(mock::exit_status(if cmd.get_program() == "reg.exe" { 1 } else { 0 }))
+/// });
+/// win_open::open_terminal(&dir).unwrap();
+/// mock::clear_runner();
+///
+/// let (program, args) = commands.borrow().last().unwrap().clone();
+/// assert_eq!(program, "cmd.exe");
+/// assert_eq!(args, ["/c", "start", r#""""#, "pwsh.exe", "-NoExit"]);
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// An error of kind [`ErrorKind::INVALID_INPUT`] is returned if `dir` isn't an existing directory,
+/// or another [`Error`] on failure.
+pub fn open_terminal<T: AsRef<OsStr>>(dir: T) -> Result<()> {
+    let mut cmd = open_terminal_command(dir)?;
+    cmd.status_without_output().into_result(cmd)
+}
+
+/// Get the command that [`open_terminal()`] runs to open a new terminal in `dir`.
+///
+/// Finding out whether Windows Terminal is available may run `reg.exe`, and detecting the
+/// fallback shell may spawn probe processes.
+///
+/// # Errors
+///
+/// An error of kind [`ErrorKind::INVALID_INPUT`] is returned if `dir` isn't an existing directory.
+pub fn open_terminal_command<T: AsRef<OsStr>>(dir: T) -> Result<Command> {
+    let dir = std::path::Path::new(dir.as_ref());
+    if !dir.is_dir() {
+        return Err(Error::new(
+            ErrorKind::INVALID_INPUT,
+            format!("'{}' is not a directory", dir.display()),
+        ));
+    }
+    let dir = std::path::absolute(dir)?;
+
+    if app::is_available(OsStr::new(WINDOWS_TERMINAL)) {
+        let mut cmd = Command::new(WINDOWS_TERMINAL);
+        cmd.arg("-d").arg(dir);
+        return Ok(cmd);
+    }
+
+    // `start` opens the shell in a new console window, in the working directory of the launcher.
+    let mut cmd = Command::new("cmd.exe");
+    cmd.current_dir(dir).args(["/c", "start"]).raw_arg("\"\"");
+    match detect_shell() {
+        WindowsShell::Powershell => cmd.args([WindowsShell::Powershell.program(), "-NoExit"]),
+        WindowsShell::Nushell => cmd.arg(WindowsShell::Nushell.program()),
+        WindowsShell::Cmd => cmd.args([WindowsShell::Cmd.program(), "/k"]),
+    };
+    cmd.creation_flags(CREATE_NO_WINDOW);
+    Ok(cmd)
+}
+
+/// The executable of Windows Terminal.
+const WINDOWS_TERMINAL: &str = "wt.exe";
+
+/// Open path with the default application and wait until the application exits.
+///
+/// This is useful for workflows that edit a (temporary) file and pick up the changes
+/// once the user is done, similar to how `git` waits for its editor.
+///
+/// # Beware
+///
+/// Single-instance applications, such as many editors, may hand the file off to an
+/// already running instance. In that case no new process is launched and this function
+/// returns right away, without waiting for the file to be closed.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// win_open::edit_and_wait("COMMIT_MSG.txt")?;
+/// let message = std::fs::read_to_string("COMMIT_MSG.txt")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] is returned on failure.
+#[cfg(feature = "shellexecute-open")]
+pub fn edit_and_wait<T: AsRef<OsStr>>(path: T) -> Result<()> {
+    let path = checked_target(path.as_ref(), &OpenOptions::new())?;
+    match ExecuteRequest::new(path).execute_process()? {
+        Some(process) => process.wait(),
+        None => Ok(()),
+    }
+}
+
+/// Open path with the default application, and attempt to bring its window to the foreground.
+///
+/// Single-instance applications often show the file in an existing window that stays in the
+/// background. This is a best-effort attempt to bring the launched application to the front,
+/// using `AllowSetForegroundWindow` and `SetForegroundWindow`.
+///
+/// # Beware
+///
+/// Windows restricts which processes may set the foreground window, so the attempt may be denied,
+/// e.g. if your app isn't in the foreground itself. If the path is handed off to an already
+/// running instance, no process is launched and its window can't be found.
+/// Neither case is reported as an error.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// win_open::that_foreground("notes.txt")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] is returned if the path can't be opened.
+#[cfg(feature = "shellexecute-open")]
+pub fn that_foreground<T: AsRef<OsStr>>(path: T) -> Result<()> {
+    let path = checked_target(path.as_ref(), &OpenOptions::new())?;
+    let request = ExecuteRequest::new(path);
+    if let Some(process) = request.execute_process()? {
+        process.bring_to_foreground();
+    }
+    Ok(())
+}
+
+/// Open path with the packaged (UWP or Store) app identified by its AppUserModelID.
+///
+/// Packaged apps, such as the modern Photos app or Windows Terminal, are launched through
+/// the activation manager (`IApplicationActivationManager::ActivateForFile`) rather than
+/// an executable path. The AppUserModelIDs of installed apps are listed by the
+/// `Get-StartApps` PowerShell command.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// win_open::with_aumid("photo.jpg", "Microsoft.Windows.Photos_8wekyb3d8bbwe!App")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] is returned if the path doesn't exist, or the app can't be activated for it.
+#[cfg(feature = "shellexecute-open")]
+pub fn with_aumid<T: AsRef<OsStr>>(path: T, aumid: impl AsRef<OsStr>) -> Result<()> {
+    let path = checked_target(path.as_ref(), &OpenOptions::new())?;
+    target::check_nul(aumid.as_ref())?;
+    activation::activate_for_file(aumid.as_ref(), &path).map(|_| ())
+}
+
+/// Open the shell item identified by the item identifier list `pidl` with its default verb.
+///
+/// Shell namespace APIs yield PIDLs (`PCIDLIST_ABSOLUTE`) rather than paths, including for
+/// virtual items without a file system path, such as Control Panel items or items on a phone.
+/// The item is opened with `ShellExecuteExW` and `SEE_MASK_IDLIST`, without converting it to a path.
+///
+/// # Safety
+/// This function is unsafe because `pidl` is passed to the Windows API as is.
+/// The caller must ensure that:
+/// - `pidl` is a valid, absolute item identifier list (i.e. relative to the desktop),
+///   such as one returned by `ILCreateFromPathW` or `SHGetIDListFromObject`.
+/// - The list is properly terminated by a zero-sized item identifier.
+/// - The memory referenced by `pidl` remains valid for the duration of the call.
+///   It isn't freed or taken over by this function, so the caller still owns it.
+///
+/// Failing to meet these conditions could result in undefined behavior, such as reading invalid memory.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::os::windows::ffi::OsStrExt;
+///
+/// #[link(name = "shell32")]
+/// extern "system" {
+///     fn ILCreateFromPathW(path: *const u16) -> *mut std::ffi::c_void;
+///     fn ILFree(pidl: *mut std::ffi::c_void);
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let path = std::env::temp_dir().join("win-open-pidl.txt");
+/// std::fs::write(&path, "Hello")?;
+///
+/// let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+/// let pidl = unsafe { ILCreateFromPathW(wide.as_ptr()) };
+/// assert!(!pidl.is_null());
+///
+/// let result = unsafe { win_open::open_pidl(pidl) };
+/// unsafe { ILFree(pidl) };
+/// result?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] of kind [`ErrorKind::INVALID_INPUT`] is returned if `pidl` is null.
+/// Otherwise a [`Error`] is returned if the item can't be opened.
+#[cfg(feature = "shellexecute-open")]
+pub unsafe fn open_pidl(pidl: *const std::ffi::c_void) -> Result<()> {
+    if pidl.is_null() {
+        return Err(Error::new(ErrorKind::INVALID_INPUT, "the PIDL is null"));
+    }
+    ExecuteRequest::new("").id_list(pidl).execute()
+}
+
+/// The verb of media handlers sending a file to a device.
+///
+/// This is the generic `play` verb, not the "Cast to Device" entry of Explorer, whose
+/// localized name and verb are provided by the streaming service and can't be invoked by name.
+#[cfg(feature = "shellexecute-open")]
+const PLAY_TO_VERB: &str = "play";
+
+/// Send the media file at `path` to a device, using the `play` verb of its handler.
+///
+/// This is the generic `play` verb of media handlers, not the localized "Cast to Device" entry
+/// of Explorer. Such verbs are dynamic: they're only available if an installed handler,
+/// such as a media player, registers them for the type of the file.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// match win_open::play_to("movie.mp4") {
+///     Ok(()) => {}
+///     Err(err) if err.is_no_association() => println!("Casting is not available"),
+///     Err(err) => return Err(err.into()),
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Casting a file passes the `play` verb to the shell.
+///
+/// ```
+/// # #[cfg(feature = "mock")]
+/// # {
+/// use std::{cell::RefCell, rc::Rc};
+/// use win_open::{mock, WindowsShell};
+///
+/// let args = Rc::new(RefCell::new(Vec::new()));
+/// let recorded = Rc::clone(&args);
+/// mock::set_runner(move |cmd| {
+///     recorded.borrow_mut().extend(cmd.get_args().map(|arg| arg.to_os_string()));
+///     Ok(mock::exit_status(0))
+/// });
+/// win_open::with_forced_shell(WindowsShell::Powershell, || {
+///     win_open::play_to("movie.mp4").unwrap();
+/// });
+/// mock::clear_runner();
+///
+/// let args = args.borrow();
+/// let verb = args.iter().position(|arg| arg == "-Verb").unwrap();
+/// assert_eq!(args[verb + 1], r#""play""#);
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] of kind [`ErrorKind::NO_ASSOCIATION`] is returned if no handler provides the verb
+/// for the file. Otherwise a [`Error`] is returned on failure.
+#[cfg(feature = "shellexecute-open")]
+pub fn play_to<T: AsRef<OsStr>>(path: T) -> Result<()> {
+    match open_with(path, OpenSpec::new().verb(PLAY_TO_VERB)) {
+        Err(err) if err.io_code() == Some(ERROR_NO_ASSOCIATION) => {
+            Err(err.with_kind(ErrorKind::NO_ASSOCIATION))
+        }
+        result => result,
+    }
+}
+
+/// Resolve the target of the shortcut (`.lnk` file) at `path`, using `IShellLinkW`.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// std::process::Command::new("powershell")
+///     .arg("-Command")
+///     .arg(r#"$s = (New-Object -ComObject WScript.Shell).CreateShortcut("$PWD\notepad.lnk");
+///             $s.TargetPath = "C:\Windows\notepad.exe"; $s.Save()"#)
+///     .status()?;
+///
+/// let target = win_open::resolve_shortcut("notepad.lnk")?;
+/// assert_eq!(target, std::path::Path::new(r"C:\Windows\notepad.exe"));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] of kind [`ErrorKind::NOT_FOUND`] is returned if the shortcut has no file system target,
+/// e.g. because it points to a URL. Otherwise a [`Error`] is returned if the shortcut can't be read.
+#[cfg(feature = "shellexecute-open")]
+pub fn resolve_shortcut<T: AsRef<std::path::Path>>(path: T) -> Result<std::path::PathBuf> {
+    target::check_nul(path.as_ref().as_os_str())?;
+    shortcut::resolve(&normalize_path(path.as_ref().as_os_str()))
+}
+
+/// Open the target of the shortcut at `path` with the default application, rather than
+/// running the shortcut itself.
+///
+/// Both shell links (`.lnk` files), pointing to files and folders, and internet shortcuts
+/// (`.url` files), pointing to URLs, are supported.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// win_open::open_shortcut_target("Report.lnk")?;
+/// win_open::open_shortcut_target("Rust.url")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] is returned if the shortcut can't be resolved, or its target can't be opened.
+#[cfg(feature = "shellexecute-open")]
+pub fn open_shortcut_target<T: AsRef<std::path::Path>>(path: T) -> Result<()> {
+    let path = path.as_ref();
+    let is_internet_shortcut = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("url"));
+
+    if is_internet_shortcut {
+        that(shortcut::internet_shortcut_url(path.as_os_str())?)
+    } else {
+        that(resolve_shortcut(path)?)
+    }
+}
+
+/// Get the friendly name of the document type of `path`, such as "PDF Document",
+/// using `AssocQueryStringW`.
+///
+/// Only the extension of `path` is used, so the path doesn't need to exist.
+/// This is useful to describe a file next to an "Open" action in a UI.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let name = win_open::document_type("notes.txt")?;
+/// assert!(!name.is_empty());
+///
+/// let err = win_open::document_type("data.unknown-extension").unwrap_err();
+/// assert_eq!(err.kind(), &win_open::ErrorKind::NO_ASSOCIATION);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] of kind [`ErrorKind::NO_ASSOCIATION`] is returned if `path` has no extension,
+/// or no file type is registered for it. Otherwise a [`Error`] is returned if the query fails.
+#[cfg(feature = "shellexecute-open")]
+pub fn document_type<T: AsRef<std::path::Path>>(path: T) -> Result<String> {
+    let path = path.as_ref();
+    target::check_nul(path.as_os_str())?;
+    let Some(extension) = path.extension() else {
+        return Err(Error::new(
+            ErrorKind::NO_ASSOCIATION,
+            format!("'{}' has no extension", path.display()),
+        ));
+    };
+
+    let mut assoc = OsString::from(".");
+    assoc.push(extension);
+    let name = assoc::query_string(&assoc, assoc::ASSOCSTR_FRIENDLYDOCNAME)?;
+    Ok(name.to_string_lossy().into_owned())
+}
+
+/// Open path with the given application using `ShellExecuteExW`.
+///
+/// The application is encoded directly from its `OsStr` form, so any path
+/// is passed to Windows without a lossy UTF-8 conversion.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let app = std::path::PathBuf::from(r"C:\Program Files\Mozilla Firefox\firefox.exe");
+/// win_open::with_detached_execute("http://rust-lang.org", app)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`Error`] of kind [`ErrorKind::INVALID_INPUT`] is returned if `path` or `app` contains
+/// a NUL character, which would otherwise truncate it.
+///
+/// ```
+/// use std::ffi::OsString;
+/// use std::os::windows::ffi::OsStringExt;
+/// use win_open::ErrorKind;
+///
+/// let path = OsString::from_wide(&[b'a' as u16, 0, b'b' as u16]);
+/// let err = win_open::with_detached_execute(path, "notepad.exe").unwrap_err();
+/// assert_eq!(err.kind(), &ErrorKind::INVALID_INPUT);
+/// ```
+///
+/// Otherwise a [`Error`] is returned if the application can't be launched.
+#[cfg(feature = "shellexecute-open")]
+pub fn with_detached_execute<T: AsRef<OsStr>>(path: T, app: impl AsRef<OsStr>) -> Result<()> {
+    check_target(path.as_ref(), &OpenOptions::new())?;
+    ExecuteRequest::new(app)
+        .parameters(normalize_path(path.as_ref()))
+        .execute()
 }
 
-/// Get a command that uses `app` to open `path`.
+/// Open path with the given application using `ShellExecuteExW`, returning the id of its process.
+///
+/// This is like [`with_detached_execute()`], but asks for a handle to the launched process,
+/// e.g. to track or terminate it later.
+///
+/// # Beware
+///
+/// Apps that hand the path off to an already running instance, or launchers that start
+/// the actual app and exit (such as the stubs of Store apps), don't provide a process.
+/// In that case `None` is returned, even though the path was opened.
 ///
 /// # Examples
 ///
 /// ```no_run
 /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
-/// let path = "http://rust-lang.org";
-/// assert!(win_open::with_command(path, "app").status()?.success());
+/// if let Some(pid) = win_open::with_detached_child_execute("notes.txt", "notepad.exe")? {
+///     assert_ne!(pid, 0);
+///     println!("Notepad is running as process {pid}");
+/// }
 /// # Ok(())
 /// # }
 /// ```
-pub fn with_command<T: AsRef<OsStr>>(path: T, app: impl Into<String>) -> Command {
-    let shell = detect_shell().as_str();
-    let mut cmd = Command::new(shell);
-
-    match shell {
-        "pwsh" => cmd
-            .arg("-NoProfile")
-            .arg("-Command")
-            .arg("Start-Process")
-            .arg(wrap_in_quotes(path.as_ref()))
-            .arg(wrap_in_quotes(app.into()))
-            .creation_flags(CREATE_NO_WINDOW),
-        "nu" => cmd
-            .arg("-c")
-            .arg(format!(
-                "open {} {}",
-                wrap_in_quotes_string(path.as_ref()),
-                wrap_in_quotes_string(app.into())
-            ))
-            .creation_flags(CREATE_NO_WINDOW),
-        "cmd" => cmd
-            .arg("/c")
-            .arg("start")
-            .raw_arg("\"\"")
-            .raw_arg(wrap_in_quotes(path))
-            .raw_arg(wrap_in_quotes(app.into()))
-            .creation_flags(CREATE_NO_WINDOW),
-        _ => panic!("No supported shell detected."),
-    };
-
-    cmd
-}
-
-/// Open path with the default application in a new thread to assure it's non-blocking.
 ///
-/// See documentation of [`that()`] for more details.
-pub fn that_in_background(path: impl AsRef<OsStr>) -> std::thread::JoinHandle<Result<()>> {
-    let path = path.as_ref().to_os_string();
-    std::thread::spawn(|| that(path))
-}
-
-/// Open path with the given application in a new thread, which is useful if
-/// the program ends up to be blocking. Otherwise, prefer [`with()`] for
-/// straightforward error handling.
+/// # Errors
 ///
-/// See documentation of [`with()`] for more details.
-pub fn with_in_background<T: AsRef<OsStr>>(
+/// A [`Error`] is returned on failure.
+#[cfg(feature = "shellexecute-open")]
+pub fn with_detached_child_execute<T: AsRef<OsStr>>(
     path: T,
-    app: impl Into<String>,
-) -> std::thread::JoinHandle<Result<()>> {
-    let path = path.as_ref().to_os_string();
-    let app = app.into();
-    std::thread::spawn(|| with(path, app))
-}
-
-fn detect_shell() -> WindowsShell {
-    *DETECTED_SHELL.get_or_init(|| match get_shell() {
-        Ok(shell) => shell,
-        Err(err) => {
-            panic!("Failed to detect a supported shell: {}", err);
-        }
-    })
-}
-
-fn get_shell() -> Result<WindowsShell> {
-    if Command::new("pwsh")
-        .arg("-Command")
-        .arg("$PSVersionTable.PSVersion")
-        .status_without_output()
-        .map_or(false, |status| status.success())
-    {
-        return "pwsh".try_into();
-    }
-
-    if Command::new("nu")
-        .arg("-c")
-        .arg("version")
-        .status_without_output()
-        .map_or(false, |status| status.success())
-    {
-        return "nu".try_into();
-    }
-
-    "cmd".try_into()
-}
-
-fn wrap_in_quotes<T: AsRef<OsStr>>(path: T) -> OsString {
-    let mut result = OsString::from("\"");
-    result.push(path);
-    result.push("\"");
-
-    result
+    app: impl AsRef<OsStr>,
+) -> Result<Option<u32>> {
+    check_target(path.as_ref(), &OpenOptions::new())?;
+    let process = ExecuteRequest::new(app)
+        .parameters(normalize_path(path.as_ref()))
+        .execute_process()?;
+    Ok(process.map(|process| process.id()))
 }
 
-fn wrap_in_quotes_string<T: AsRef<OsStr>>(path: T) -> String {
-    let path = path.as_ref().to_string_lossy();
-    format!("\"{}\"", path)
-}
-
-/// Open path with the default application using a detached process. which is useful if
-/// the program ends up to be blocking or want to out-live your app
+/// Whether to open with `ShellExecuteExW`, rather than by running a shell command.
 ///
-/// See documentation of [`that()`] for more details.
-pub fn that_detached(path: impl AsRef<OsStr>) -> Result<()> {
-    #[cfg(not(feature = "shellexecute"))]
-    {
-        let mut last_err = None;
-        for mut cmd in commands(path) {
-            match cmd.spawn_detached() {
-                Ok(_) => {
-                    return Ok(());
-                }
-                Err(err) => last_err = Some(err),
-            }
-        }
-        Err(last_err.map_or_else(
-            || Error::new(ErrorKind::NO_LAUNCHER, ""),
-            |err| Error::new(ErrorKind::IO, err.to_string().as_str()),
-        ))
+/// `ShellExecuteExW` is used unless a shell was chosen explicitly, PowerShell should invoke
+/// the app directly, or a mock runner is set, which only sees commands.
+#[cfg(feature = "shellexecute-open")]
+fn uses_execute(options: &OpenOptions) -> bool {
+    #[cfg(feature = "mock")]
+    if mock::is_active() {
+        return false;
     }
+    options.explicit_shell().is_none() && !options.invokes_directly()
+}
 
-    #[cfg(feature = "shellexecute")]
-    {
-        that_detached_execute(path)
-    }
+/// Whether `app` is an absolute path to an `.exe`, which can be launched without a shell.
+#[cfg(feature = "shellexecute-open")]
+fn is_absolute_exe(app: &OsStr) -> bool {
+    let app = std::path::Path::new(app);
+    app.is_absolute()
+        && app
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"))
 }
 
-/// Open path with the given application using a detached process, which is useful if
-/// the program ends up to be blocking or want to out-live your app. Otherwise, prefer [`with()`] for
-/// straightforward error handling.
+/// Launch `app` with `path` as its only argument using `CreateProcessW`, without a shell.
 ///
-/// See documentation of [`with()`] for more details.
-pub fn with_detached<T: AsRef<OsStr>>(path: T, app: impl Into<String>) -> Result<()> {
-    #[cfg(not(feature = "shellexecute"))]
-    {
-        let mut last_err = None;
-        let mut cmd = with_command(path, app);
+/// The process isn't waited for; its handles are closed right away.
+#[cfg(feature = "shellexecute-open")]
+fn create_process(app: &OsStr, path: &OsStr) -> Result<()> {
+    let path = target::resolve(path)?;
+    let mut command_line = wrap_in_quotes(app);
+    command_line.push(" ");
+    command_line.push(wrap_in_quotes(path));
 
-        // Try spawning the detached process
-        match cmd.spawn_detached() {
-            Ok(_) => {
-                return Ok(()); // Successfully spawned the detached process
-            }
-            Err(err) => {
-                last_err = Some(err); // Store the error if spawning fails
-            }
-        }
+    let app = wide(app);
+    // `CreateProcessW` may modify the command line in place, so it must be writable.
+    let mut command_line = wide(command_line);
+    let startup_info = ffi::STARTUPINFOW {
+        cb: std::mem::size_of::<ffi::STARTUPINFOW>() as u32,
+        ..unsafe { std::mem::zeroed() }
+    };
+    let mut process_info: ffi::PROCESS_INFORMATION = unsafe { std::mem::zeroed() };
 
-        Err(last_err.map_or_else(
-            || Error::new(ErrorKind::NO_LAUNCHER, ""),
-            |err| Error::new(ErrorKind::IO, err.to_string().as_str()),
-        ))
+    let created = unsafe {
+        ffi::CreateProcessW(
+            app.as_ptr(),
+            command_line.as_mut_ptr(),
+            std::ptr::null(),
+            std::ptr::null(),
+            0,
+            0,
+            std::ptr::null(),
+            std::ptr::null(),
+            &startup_info,
+            &mut process_info,
+        )
+    };
+    if created == 0 {
+        return Err(Error::last_os_error());
     }
 
-    #[cfg(feature = "shellexecute")]
-    {
-        with_detached_execute(path, app)
+    unsafe {
+        ffi::CloseHandle(process_info.hThread);
+        ffi::CloseHandle(process_info.hProcess);
     }
+    Ok(())
 }
 
-trait IntoResult<T> {
-    fn into_result(self, cmd: Command) -> T;
+/// Open `target` as described by `spec` using `ShellExecuteExW`.
+#[cfg(feature = "shellexecute-open")]
+fn open_with_execute(target: &OsStr, spec: &OpenSpec) -> Result<()> {
+    open_with_request(target, spec).execute()
 }
 
-impl IntoResult<Result<()>> for std::io::Result<std::process::ExitStatus> {
-    fn into_result(self, cmd: Command) -> Result<()> {
-        match self {
-            Ok(status) if status.success() => Ok(()),
-            Ok(status) => Err(Error::new(
-                ErrorKind::COMMAND_FAILED,
-                format!("{cmd:?} ({})", status).as_str(),
-            )),
-            Err(err) => Err(err.into()),
+/// Builds the `ShellExecuteExW` request opening `target` as described by `spec`.
+#[cfg(feature = "shellexecute-open")]
+fn open_with_request(target: &OsStr, spec: &OpenSpec) -> ExecuteRequest {
+    let mut parameters: Vec<&OsStr> = Vec::new();
+    let mut request = match spec.get_app() {
+        Some(app) => {
+            parameters.push(target);
+            ExecuteRequest::new(app)
         }
-    }
-}
-
-trait CommandExt {
-    fn status_without_output(&mut self) -> std::io::Result<std::process::ExitStatus>;
-    fn spawn_detached(&mut self) -> std::io::Result<()>;
-}
+        None => ExecuteRequest::new(target),
+    };
+    parameters.extend(spec.get_args().iter().map(OsString::as_os_str));
 
-impl CommandExt for Command {
-    fn status_without_output(&mut self) -> std::io::Result<std::process::ExitStatus> {
-        self.stdin(Stdio::null())
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status()
+    if !parameters.is_empty() {
+        let mut list = OsString::new();
+        for (i, parameter) in parameters.into_iter().enumerate() {
+            if i > 0 {
+                list.push(" ");
+            }
+            list.push(wrap_in_quotes(parameter));
+        }
+        request = request.parameters(list);
     }
-
-    fn spawn_detached(&mut self) -> std::io::Result<()> {
-        // This is pretty much lifted from the implementation in Alacritty:
-        // https://github.com/alacritty/alacritty/blob/b9c886872d1202fc9302f68a0bedbb17daa35335/alacritty/src/daemon.rs
-
-        self.stdin(Stdio::null())
-            .stdout(Stdio::null())
-            .stderr(Stdio::null());
-
-        use std::os::windows::process::CommandExt;
-        const CREATE_NEW_PROCESS_GROUP: u32 = 0x00000200;
-        const CREATE_NO_WINDOW: u32 = 0x08000000;
-        self.creation_flags(CREATE_NEW_PROCESS_GROUP | CREATE_NO_WINDOW);
-        self.spawn().map(|_| ())
+    if let Some(verb) = spec.get_verb() {
+        request = request.verb(verb);
     }
+    if let Some(show) = spec.get_show() {
+        request = request.show(show.show_command());
+    }
+    if let Some(dir) = spec.get_dir() {
+        request = request.directory(dir);
+    }
+
+    request.hwnd(spec.get_parent_window())
 }
 
-#[cfg(feature = "shellexecute")]
-fn that_detached_execute<T: AsRef<OsStr>>(path: T) -> Result<()> {
-    let path = path.as_ref();
-    let is_dir = std::fs::metadata(path).map(|f| f.is_dir()).unwrap_or(false);
+/// Resolves a local path to its absolute, long form, so the shell isn't confused
+/// by a different working directory or by 8.3 short names.
+///
+/// URLs, and paths that can't be resolved, are returned unchanged.
+#[cfg(any(feature = "shellexecute-open", feature = "shellexecute-reveal"))]
+fn normalize_path(path: &OsStr) -> OsString {
+    if target::is_url(path) {
+        return path.to_os_string();
+    }
 
     let path = wide(path);
-
-    if is_dir {
-        unsafe { ffi::CoInitialize(std::ptr::null()) };
-        let folder = unsafe { ffi::ILCreateFromPathW(path.as_ptr()) };
-        unsafe { SHOpenFolderAndSelectItems(folder, Some(&[folder]), 0)? };
-        return Ok(());
+    let full = match unsafe {
+        wide_buffer(|buf, len| ffi::GetFullPathNameW(path.as_ptr(), len, buf, std::ptr::null_mut()))
+    } {
+        Some(full) => full,
+        None => return from_wide(&path),
     };
 
-    let mut info = ffi::SHELLEXECUTEINFOW {
-        cbSize: std::mem::size_of::<ffi::SHELLEXECUTEINFOW>() as _,
-        nShow: ffi::SW_SHOWNORMAL,
-        lpVerb: std::ptr::null(),
-        lpClass: std::ptr::null(),
-        lpFile: path.as_ptr(),
-        ..unsafe { std::mem::zeroed() }
-    };
-
-    unsafe { ShellExecuteExW(&mut info) }
+    let full = wide(full);
+    // Only succeeds for paths that exist; keep the full path otherwise.
+    match unsafe { wide_buffer(|buf, len| ffi::GetLongPathNameW(full.as_ptr(), buf, len)) } {
+        Some(long) => long,
+        None => from_wide(&full),
+    }
 }
 
-#[cfg(feature = "shellexecute")]
-pub fn with_detached_execute<T: AsRef<OsStr>>(path: T, app: impl Into<String>) -> Result<()> {
-    let app = wide(app.into());
-    let path = wide(path);
-
-    let mut info = ffi::SHELLEXECUTEINFOW {
-        cbSize: std::mem::size_of::<ffi::SHELLEXECUTEINFOW>() as _,
-        nShow: ffi::SW_SHOWNORMAL,
-        lpFile: app.as_ptr(),
-        lpParameters: path.as_ptr(),
-        ..unsafe { std::mem::zeroed() }
-    };
+/// Calls a Win32 function that writes a string of unknown length into a wide buffer.
+///
+/// The function receives the buffer and its length in characters, and returns the number of
+/// characters written, or the required length (including the null character) if the buffer
+/// is too small. A return value of `0` indicates failure.
+///
+/// # Safety
+/// The caller must ensure `f` writes at most the given length of characters into the buffer.
+#[cfg(any(feature = "shellexecute-open", feature = "shellexecute-reveal"))]
+unsafe fn wide_buffer(mut f: impl FnMut(*mut u16, u32) -> u32) -> Option<OsString> {
+    let mut buf = vec![0u16; 260];
+    loop {
+        let len = f(buf.as_mut_ptr(), buf.len() as u32) as usize;
+        if len == 0 {
+            return None;
+        } else if len < buf.len() {
+            return Some(from_wide(&buf[..len]));
+        }
+        buf.resize(len, 0);
+    }
+}
 
-    unsafe { ShellExecuteExW(&mut info) }
+/// Decodes a wide string, stopping at the first null character.
+#[cfg(any(feature = "shellexecute-open", feature = "shellexecute-reveal"))]
+fn from_wide(input: &[u16]) -> OsString {
+    use std::os::windows::ffi::OsStringExt;
+    let len = input.iter().position(|&c| c == 0).unwrap_or(input.len());
+    OsString::from_wide(&input[..len])
 }
 
 /// Encodes as wide and adds a null character.
-#[cfg(feature = "shellexecute")]
+#[cfg(any(feature = "shellexecute-open", feature = "shellexecute-reveal"))]
 #[inline]
 fn wide<T: AsRef<OsStr>>(input: T) -> Vec<u16> {
     use std::os::windows::ffi::OsStrExt;
@@ -475,17 +4163,22 @@ fn wide<T: AsRef<OsStr>>(input: T) -> Vec<u16> {
 /// Failing to meet these conditions could result in undefined behavior, such as dereferencing invalid memory
 /// or passing incorrect data to the Windows API, which could lead to incorrect results or crashes.
 #[allow(non_snake_case)]
-#[cfg(feature = "shellexecute")]
+#[cfg(feature = "shellexecute-open")]
 unsafe fn ShellExecuteExW(info: *mut ffi::SHELLEXECUTEINFOW) -> Result<()> {
     // ShellExecuteExW returns TRUE (i.e 1) on success
     // https://learn.microsoft.com/en-us/windows/win32/api/shellapi/nf-shellapi-shellexecuteexw#remarks
-    if ffi::ShellExecuteExW(info) == 1 {
-        Ok(())
-    } else {
-        Err(Error::new(
-            ErrorKind::IO,
-            std::io::Error::last_os_error().to_string().as_str(),
-        ))
+    let os_error = (ffi::ShellExecuteExW(info) != 1).then(std::io::Error::last_os_error);
+
+    // `hInstApp` carries a more precise `SE_ERR_*` code on some failures,
+    // which may be reported even when the call nominally succeeds.
+    match (Error::from_shell_execute_code((*info).hInstApp), os_error) {
+        // Keep the OS error as the source, so its code can still be checked.
+        (Some(err), Some(os_error)) => Err(Error::from(os_error)
+            .with_kind(*err.kind())
+            .context(err.message())),
+        (Some(err), None) => Err(err),
+        (None, Some(os_error)) => Err(os_error.into()),
+        (None, None) => Ok(()),
     }
 }
 
@@ -504,7 +4197,7 @@ unsafe fn ShellExecuteExW(info: *mut ffi::SHELLEXECUTEINFOW) -> Result<()> {
 ///
 /// Failing to adhere to these safety guarantees could result in undefined behavior, such as dereferencing invalid memory.
 #[allow(non_snake_case)]
-#[cfg(feature = "shellexecute")]
+#[cfg(feature = "shellexecute-reveal")]
 unsafe fn SHOpenFolderAndSelectItems(
     pidlfolder: *const ffi::ITEMIDLIST,
     apidl: Option<&[*const ffi::ITEMIDLIST]>,
@@ -519,16 +4212,11 @@ unsafe fn SHOpenFolderAndSelectItems(
         dwflags,
     ) {
         0 => Ok(()),
-        error_code => Err(Error::new(
-            ErrorKind::IO,
-            std::io::Error::from_raw_os_error(error_code)
-                .to_string()
-                .as_str(),
-        )),
+        error_code => Err(Error::from_os_error(error_code)),
     }
 }
 
-#[cfg(feature = "shellexecute")]
+#[cfg(any(feature = "shellexecute-open", feature = "shellexecute-reveal"))]
 #[allow(non_snake_case)]
 mod ffi {
     /// Activates and displays a window.
@@ -538,6 +4226,58 @@ mod ffi {
     /// <https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-showwindow>
     pub const SW_SHOWNORMAL: i32 = 1;
 
+    /// Displays the window as a minimized window, without activating it.
+    ///
+    /// <https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-showwindow>
+    pub const SW_SHOWMINNOACTIVE: i32 = 7;
+
+    /// Do not display any user interface, such as error messages or security prompts.
+    ///
+    /// <https://learn.microsoft.com/en-us/windows/win32/api/shellapi/ns-shellapi-shellexecuteinfow>
+    pub const SEE_MASK_FLAG_NO_UI: u32 = 0x00000400;
+
+    /// Wait for the execute operation to complete before returning.
+    ///
+    /// <https://learn.microsoft.com/en-us/windows/win32/api/shellapi/ns-shellapi-shellexecuteinfow>
+    pub const SEE_MASK_NOASYNC: u32 = 0x00000100;
+
+    /// Wait for the DDE conversation to terminate before returning.
+    /// Superseded by (and equal to) [`SEE_MASK_NOASYNC`].
+    ///
+    /// <https://learn.microsoft.com/en-us/windows/win32/api/shellapi/ns-shellapi-shellexecuteinfow>
+    pub const SEE_MASK_FLAG_DDEWAIT: u32 = 0x00000100;
+
+    /// Keep the handle of the launched process open in `hProcess`.
+    ///
+    /// <https://learn.microsoft.com/en-us/windows/win32/api/shellapi/ns-shellapi-shellexecuteinfow>
+    pub const SEE_MASK_NOCLOSEPROCESS: u32 = 0x00000040;
+
+    /// Wait without a time-out.
+    ///
+    /// <https://learn.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-waitforsingleobject>
+    pub const INFINITE: u32 = 0xFFFFFFFF;
+
+    /// The wait failed.
+    ///
+    /// <https://learn.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-waitforsingleobject>
+    pub const WAIT_FAILED: u32 = 0xFFFFFFFF;
+
+    /// Keep track of the number of times the file is opened, adding it to the recent files lists.
+    ///
+    /// <https://learn.microsoft.com/en-us/windows/win32/api/shellapi/ns-shellapi-shellexecuteinfow>
+    pub const SEE_MASK_FLAG_LOG_USAGE: u32 = 0x04000000;
+
+    /// Use the class name given by `lpClass`.
+    ///
+    /// <https://learn.microsoft.com/en-us/windows/win32/api/shellapi/ns-shellapi-shellexecuteinfow>
+    pub const SEE_MASK_CLASSNAME: u32 = 0x00000001;
+
+    /// Use the item identifier list given by `lpIDList`, instead of `lpFile`.
+    ///
+    /// <https://learn.microsoft.com/en-us/windows/win32/api/shellapi/ns-shellapi-shellexecuteinfow>
+    pub const SEE_MASK_IDLIST: u32 = 0x00000004;
+
+    #[cfg(feature = "shellexecute-open")]
     // Taken from https://docs.rs/windows-sys/latest/windows_sys/
     #[cfg_attr(not(target_arch = "x86"), repr(C))]
     #[cfg_attr(target_arch = "x86", repr(C, packed(1)))]
@@ -559,6 +4299,7 @@ mod ffi {
         pub hProcess: isize,
     }
 
+    #[cfg(feature = "shellexecute-open")]
     // Taken from https://docs.rs/windows-sys/latest/windows_sys/
     #[cfg_attr(not(target_arch = "x86"), repr(C))]
     #[cfg_attr(target_arch = "x86", repr(C, packed(1)))]
@@ -567,6 +4308,7 @@ mod ffi {
         pub hMonitor: isize,
     }
 
+    #[cfg(feature = "shellexecute-reveal")]
     // Taken from https://microsoft.github.io/windows-docs-rs/doc/windows/
     #[repr(C, packed(1))]
     pub struct SHITEMID {
@@ -574,16 +4316,270 @@ mod ffi {
         pub abID: [u8; 1],
     }
 
+    #[cfg(feature = "shellexecute-reveal")]
     // Taken from https://microsoft.github.io/windows-docs-rs/doc/windows/
     #[repr(C, packed(1))]
     pub struct ITEMIDLIST {
         pub mkid: SHITEMID,
     }
 
+    #[cfg(feature = "shellexecute-open")]
+    // Taken from https://docs.rs/windows-sys/latest/windows_sys/
+    #[repr(C)]
+    pub struct STARTUPINFOW {
+        pub cb: u32,
+        pub lpReserved: *mut u16,
+        pub lpDesktop: *mut u16,
+        pub lpTitle: *mut u16,
+        pub dwX: u32,
+        pub dwY: u32,
+        pub dwXSize: u32,
+        pub dwYSize: u32,
+        pub dwXCountChars: u32,
+        pub dwYCountChars: u32,
+        pub dwFillAttribute: u32,
+        pub dwFlags: u32,
+        pub wShowWindow: u16,
+        pub cbReserved2: u16,
+        pub lpReserved2: *mut u8,
+        pub hStdInput: isize,
+        pub hStdOutput: isize,
+        pub hStdError: isize,
+    }
+
+    #[cfg(feature = "shellexecute-open")]
+    // Taken from https://docs.rs/windows-sys/latest/windows_sys/
+    #[repr(C)]
+    pub struct PROCESS_INFORMATION {
+        pub hProcess: isize,
+        pub hThread: isize,
+        pub dwProcessId: u32,
+        pub dwThreadId: u32,
+    }
+
+    #[cfg(feature = "shellexecute-open")]
+    // Taken from https://docs.rs/windows-sys/latest/windows_sys/
+    #[repr(C)]
+    pub struct JOBOBJECT_BASIC_LIMIT_INFORMATION {
+        pub PerProcessUserTimeLimit: i64,
+        pub PerJobUserTimeLimit: i64,
+        pub LimitFlags: u32,
+        pub MinimumWorkingSetSize: usize,
+        pub MaximumWorkingSetSize: usize,
+        pub ActiveProcessLimit: u32,
+        pub Affinity: usize,
+        pub PriorityClass: u32,
+        pub SchedulingClass: u32,
+    }
+
+    #[cfg(feature = "shellexecute-open")]
+    // Taken from https://docs.rs/windows-sys/latest/windows_sys/
+    #[repr(C)]
+    pub struct IO_COUNTERS {
+        pub ReadOperationCount: u64,
+        pub WriteOperationCount: u64,
+        pub OtherOperationCount: u64,
+        pub ReadTransferCount: u64,
+        pub WriteTransferCount: u64,
+        pub OtherTransferCount: u64,
+    }
+
+    #[cfg(feature = "shellexecute-open")]
+    // Taken from https://docs.rs/windows-sys/latest/windows_sys/
+    #[repr(C)]
+    pub struct JOBOBJECT_EXTENDED_LIMIT_INFORMATION {
+        pub BasicLimitInformation: JOBOBJECT_BASIC_LIMIT_INFORMATION,
+        pub IoInfo: IO_COUNTERS,
+        pub ProcessMemoryLimit: usize,
+        pub JobMemoryLimit: usize,
+        pub PeakProcessMemoryUsed: usize,
+        pub PeakJobMemoryUsed: usize,
+    }
+
+    /// The class of shell links (`.lnk` files).
+    ///
+    /// <https://learn.microsoft.com/en-us/windows/win32/shell/links>
+    #[cfg(feature = "shellexecute-open")]
+    pub const CLSID_SHELL_LINK: GUID = GUID::new(0x00021401, 0, 0, [0xC0, 0, 0, 0, 0, 0, 0, 0x46]);
+
+    /// The identifier of the `IShellLinkW` interface.
+    #[cfg(feature = "shellexecute-open")]
+    pub const IID_ISHELL_LINK_W: GUID = GUID::new(0x000214F9, 0, 0, [0xC0, 0, 0, 0, 0, 0, 0, 0x46]);
+
+    /// The identifier of the `IPersistFile` interface.
+    #[cfg(feature = "shellexecute-open")]
+    pub const IID_IPERSIST_FILE: GUID = GUID::new(0x0000010B, 0, 0, [0xC0, 0, 0, 0, 0, 0, 0, 0x46]);
+
+    /// The class of the activation manager of packaged apps.
+    ///
+    /// <https://learn.microsoft.com/en-us/windows/win32/api/shobjidl_core/nn-shobjidl_core-iapplicationactivationmanager>
+    #[cfg(feature = "shellexecute-open")]
+    pub const CLSID_APPLICATION_ACTIVATION_MANAGER: GUID = GUID::new(
+        0x45BA127D,
+        0x10A8,
+        0x46EA,
+        [0x8A, 0xB7, 0x56, 0xEA, 0x90, 0x78, 0x94, 0x3C],
+    );
+
+    /// The identifier of the `IApplicationActivationManager` interface.
+    #[cfg(feature = "shellexecute-open")]
+    pub const IID_IAPPLICATION_ACTIVATION_MANAGER: GUID = GUID::new(
+        0x2E941141,
+        0x7F97,
+        0x4756,
+        [0xBA, 0x1D, 0x9D, 0xEC, 0xDE, 0x89, 0x4A, 0x3D],
+    );
+
+    /// The identifier of the `IShellItem` interface.
+    #[cfg(feature = "shellexecute-open")]
+    pub const IID_ISHELL_ITEM: GUID = GUID::new(
+        0x43826D1E,
+        0xE718,
+        0x42EE,
+        [0xBC, 0x55, 0xA1, 0xE2, 0x61, 0xC3, 0x7B, 0xFE],
+    );
+
+    /// The identifier of the `IShellItemArray` interface.
+    #[cfg(feature = "shellexecute-open")]
+    pub const IID_ISHELL_ITEM_ARRAY: GUID = GUID::new(
+        0xB63EA76D,
+        0x1F85,
+        0x456F,
+        [0xA1, 0x9C, 0x48, 0x15, 0x9E, 0xFA, 0x85, 0x8B],
+    );
+
+    /// Create the object in a separate process on the same machine.
+    ///
+    /// <https://learn.microsoft.com/en-us/windows/win32/api/wtypesbase/ne-wtypesbase-clsctx>
+    pub const CLSCTX_LOCAL_SERVER: u32 = 0x4;
+
+    /// Create the object in the same process.
+    ///
+    /// <https://learn.microsoft.com/en-us/windows/win32/api/wtypesbase/ne-wtypesbase-clsctx>
+    pub const CLSCTX_INPROC_SERVER: u32 = 0x1;
+
+    /// Open the file for reading only.
+    ///
+    /// <https://learn.microsoft.com/en-us/windows/win32/stg/stgm-constants>
+    pub const STGM_READ: u32 = 0x0;
+
+    // Taken from https://docs.rs/windows-sys/latest/windows_sys/
+    #[repr(C)]
+    pub struct GUID {
+        pub data1: u32,
+        pub data2: u16,
+        pub data3: u16,
+        pub data4: [u8; 8],
+    }
+
+    impl GUID {
+        pub const fn new(data1: u32, data2: u16, data3: u16, data4: [u8; 8]) -> Self {
+            Self {
+                data1,
+                data2,
+                data3,
+                data4,
+            }
+        }
+    }
+
+    /// The methods of `IUnknown`, which every COM interface starts with.
+    #[repr(C)]
+    pub struct IUnknownVtbl {
+        pub QueryInterface: unsafe extern "system" fn(
+            this: *mut core::ffi::c_void,
+            riid: *const GUID,
+            ppvobject: *mut *mut core::ffi::c_void,
+        ) -> i32,
+        pub AddRef: unsafe extern "system" fn(this: *mut core::ffi::c_void) -> u32,
+        pub Release: unsafe extern "system" fn(this: *mut core::ffi::c_void) -> u32,
+    }
+
+    /// The leading methods of `IShellLinkW`; the remaining ones are unused.
+    #[cfg(feature = "shellexecute-open")]
+    #[repr(C)]
+    pub struct IShellLinkWVtbl {
+        pub base: IUnknownVtbl,
+        pub GetPath: unsafe extern "system" fn(
+            this: *mut core::ffi::c_void,
+            pszfile: *mut u16,
+            cch: i32,
+            pfd: *mut core::ffi::c_void,
+            fflags: u32,
+        ) -> i32,
+    }
+
+    /// The leading methods of `IPersistFile`; the remaining ones are unused.
+    #[cfg(feature = "shellexecute-open")]
+    #[repr(C)]
+    pub struct IPersistFileVtbl {
+        pub base: IUnknownVtbl,
+        pub GetClassID:
+            unsafe extern "system" fn(this: *mut core::ffi::c_void, pclassid: *mut GUID) -> i32,
+        pub IsDirty: unsafe extern "system" fn(this: *mut core::ffi::c_void) -> i32,
+        pub Load: unsafe extern "system" fn(
+            this: *mut core::ffi::c_void,
+            pszfilename: *const u16,
+            dwmode: u32,
+        ) -> i32,
+    }
+
+    /// The leading methods of `IApplicationActivationManager`; the remaining one is unused.
+    #[cfg(feature = "shellexecute-open")]
+    #[repr(C)]
+    pub struct IApplicationActivationManagerVtbl {
+        pub base: IUnknownVtbl,
+        pub ActivateApplication: unsafe extern "system" fn(
+            this: *mut core::ffi::c_void,
+            appusermodelid: *const u16,
+            arguments: *const u16,
+            options: u32,
+            processid: *mut u32,
+        ) -> i32,
+        pub ActivateForFile: unsafe extern "system" fn(
+            this: *mut core::ffi::c_void,
+            appusermodelid: *const u16,
+            itemarray: *mut core::ffi::c_void,
+            verb: *const u16,
+            processid: *mut u32,
+        ) -> i32,
+    }
+
+    #[cfg(feature = "shellexecute-open")]
     #[link(name = "shell32")]
     extern "system" {
         pub fn ShellExecuteExW(info: *mut SHELLEXECUTEINFOW) -> isize;
+        pub fn SHCreateItemFromParsingName(
+            pszpath: *const u16,
+            pbc: *mut core::ffi::c_void,
+            riid: *const GUID,
+            ppv: *mut *mut core::ffi::c_void,
+        ) -> i32;
+        pub fn SHCreateShellItemArrayFromShellItem(
+            psi: *mut core::ffi::c_void,
+            riid: *const GUID,
+            ppv: *mut *mut core::ffi::c_void,
+        ) -> i32;
+    }
+
+    #[cfg(feature = "shellexecute-open")]
+    #[link(name = "shlwapi")]
+    extern "system" {
+        pub fn AssocQueryStringW(
+            flags: u32,
+            str: u32,
+            pszassoc: *const u16,
+            pszextra: *const u16,
+            pszout: *mut u16,
+            pcchout: *mut u32,
+        ) -> i32;
+    }
+
+    #[cfg(feature = "shellexecute-reveal")]
+    #[link(name = "shell32")]
+    extern "system" {
         pub fn ILCreateFromPathW(pszpath: *const u16) -> *mut ITEMIDLIST;
+        pub fn ILFree(pidl: *mut ITEMIDLIST);
         pub fn SHOpenFolderAndSelectItems(
             pidlfolder: *const ITEMIDLIST,
             cidl: u32,
@@ -592,8 +4588,161 @@ mod ffi {
         ) -> i32;
     }
 
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn GetFullPathNameW(
+            lpfilename: *const u16,
+            nbufferlength: u32,
+            lpbuffer: *mut u16,
+            lpfilepart: *mut *mut u16,
+        ) -> u32;
+        pub fn GetLongPathNameW(
+            lpszshortpath: *const u16,
+            lpszlongpath: *mut u16,
+            cchbuffer: u32,
+        ) -> u32;
+    }
+
+    #[cfg(feature = "shellexecute-open")]
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn WaitForSingleObject(hhandle: isize, dwmilliseconds: u32) -> u32;
+        pub fn GetProcessId(process: isize) -> u32;
+        pub fn CloseHandle(hobject: isize) -> i32;
+        pub fn CreateProcessW(
+            lpapplicationname: *const u16,
+            lpcommandline: *mut u16,
+            lpprocessattributes: *const core::ffi::c_void,
+            lpthreadattributes: *const core::ffi::c_void,
+            binherithandles: i32,
+            dwcreationflags: u32,
+            lpenvironment: *const core::ffi::c_void,
+            lpcurrentdirectory: *const u16,
+            lpstartupinfo: *const STARTUPINFOW,
+            lpprocessinformation: *mut PROCESS_INFORMATION,
+        ) -> i32;
+        pub fn CreateJobObjectW(
+            lpjobattributes: *const core::ffi::c_void,
+            lpname: *const u16,
+        ) -> isize;
+        pub fn SetInformationJobObject(
+            hjob: isize,
+            jobobjectinformationclass: i32,
+            lpjobobjectinformation: *const core::ffi::c_void,
+            cbjobobjectinformationlength: u32,
+        ) -> i32;
+        pub fn AssignProcessToJobObject(hjob: isize, hprocess: isize) -> i32;
+        pub fn IsProcessInJob(processhandle: isize, jobhandle: isize, result: *mut i32) -> i32;
+    }
+
+    #[cfg(feature = "shellexecute-open")]
+    #[link(name = "user32")]
+    extern "system" {
+        pub fn WaitForInputIdle(hprocess: isize, dwmilliseconds: u32) -> u32;
+        pub fn AllowSetForegroundWindow(dwprocessid: u32) -> i32;
+        pub fn SetForegroundWindow(hwnd: isize) -> i32;
+        pub fn EnumWindows(
+            lpenumfunc: extern "system" fn(hwnd: isize, lparam: isize) -> i32,
+            lparam: isize,
+        ) -> i32;
+        pub fn GetWindowThreadProcessId(hwnd: isize, lpdwprocessid: *mut u32) -> u32;
+        pub fn IsWindowVisible(hwnd: isize) -> i32;
+    }
+
     #[link(name = "ole32")]
     extern "system" {
         pub fn CoInitialize(pvreserved: *const core::ffi::c_void) -> i32;
     }
+
+    #[cfg(feature = "shellexecute-open")]
+    #[link(name = "ole32")]
+    extern "system" {
+        pub fn CoCreateInstance(
+            rclsid: *const GUID,
+            punkouter: *mut core::ffi::c_void,
+            dwclscontext: u32,
+            riid: *const GUID,
+            ppv: *mut *mut core::ffi::c_void,
+        ) -> i32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "shellexecute-open")]
+    fn executes_unless_a_shell_is_chosen() {
+        assert!(uses_execute(&OpenOptions::new()));
+        assert!(!uses_execute(OpenOptions::new().shell(WindowsShell::Cmd)));
+        let forced = with_forced_shell(WindowsShell::Cmd, || uses_execute(&OpenOptions::new()));
+        assert!(!forced);
+    }
+
+    #[test]
+    #[cfg(feature = "shellexecute-open")]
+    fn play_to_passes_the_play_verb() {
+        let mut spec = OpenSpec::new();
+        spec.verb(PLAY_TO_VERB);
+        let request = open_with_request(OsStr::new("movie.mp4"), &spec);
+        let info = request.info();
+        let verb = unsafe { std::slice::from_raw_parts(info.lpVerb, 5) };
+        assert_eq!(verb, wide("play"));
+    }
+
+    #[test]
+    fn extended_device_paths_are_device_paths() {
+        assert!(target::is_device_path(OsStr::new(r"\\?\PhysicalDrive0")));
+        assert!(target::is_device_path(OsStr::new(
+            r"\\?\GLOBALROOT\Device\Null"
+        )));
+        assert!(target::is_device_path(OsStr::new(r"\??\PhysicalDrive0")));
+        assert!(target::is_device_path(OsStr::new(r"\\?\C:")));
+        assert!(!target::is_device_path(OsStr::new(r"\\?\C:\notes.txt.")));
+        assert!(!target::is_device_path(OsStr::new(
+            r"\\?\UNC\server\share\notes.txt"
+        )));
+        assert!(!target::is_device_path(OsStr::new(
+            r"\\?\Volume{26a21bda-a627-11d7-9931-806e6f6e6963}\notes.txt"
+        )));
+    }
+
+    #[test]
+    fn file_urls_are_not_app_uris() {
+        assert!(target::is_app_uri(OsStr::new("ms-settings:display")));
+        assert!(target::is_app_uri(OsStr::new("shell:Downloads")));
+        assert!(!target::is_app_uri(OsStr::new("https://rust-lang.org")));
+        assert!(!target::is_app_uri(OsStr::new("file:///C:/notes.txt")));
+    }
+
+    #[test]
+    fn detached_launchers_get_no_console_and_a_new_process_group() {
+        assert_eq!(DETACHED_CREATION_FLAGS, 0x00000008 | 0x00000200);
+        assert_eq!(DETACHED_CREATION_FLAGS & CREATE_NO_WINDOW, 0);
+    }
+
+    #[test]
+    fn captured_stderr_is_included_in_the_error() {
+        use std::os::windows::process::ExitStatusExt;
+
+        let output = std::process::Output {
+            status: ExitStatus::from_raw(1),
+            stdout: Vec::new(),
+            stderr: b"The system cannot find the file specified.\r\n".to_vec(),
+        };
+        let err = Ok(output).into_result(Command::new("cmd.exe")).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::COMMAND_FAILED);
+        assert!(err
+            .message()
+            .ends_with(": The system cannot find the file specified."));
+    }
+
+    #[cfg(any(feature = "shellexecute-open", feature = "shellexecute-reveal"))]
+    #[test]
+    fn normalize_path_makes_relative_paths_absolute() {
+        let path = normalize_path(OsStr::new(r"docs\notes.txt"));
+        assert!(std::path::Path::new(&path).is_absolute());
+        assert!(path.to_string_lossy().ends_with(r"\docs\notes.txt"));
+    }
 }
@@ -0,0 +1,96 @@
+//! Substitute the command runner to test launch behavior without running real programs.
+//!
+//! Once a runner is set with [`set_runner()`], every command the crate would run or
+//! spawn on the current thread is passed to it instead, including the shell detection probes.
+//! The runner can record what *would* run and simulate success, failure or nonzero exits.
+//!
+//! Runners are thread-local, so functions that open in a new thread,
+//! such as [`that_in_background()`](crate::that_in_background), are not affected.
+//!
+//! # Examples
+//!
+//! Simulate every launcher exiting with a nonzero status.
+//!
+//! ```
+//! use win_open::{mock, ErrorKind};
+//!
+//! mock::set_runner(|_| Ok(mock::exit_status(1)));
+//! let err = win_open::that("http://rust-lang.org").unwrap_err();
+//! assert_eq!(err.kind(), &ErrorKind::COMMAND_FAILED);
+//! mock::clear_runner();
+//! ```
+//!
+//! Simulate every launcher failing to start.
+//!
+//! ```
+//! use std::io;
+//! use win_open::{mock, ErrorKind};
+//!
+//! mock::set_runner(|_| Err(io::Error::from(io::ErrorKind::NotFound)));
+//! let err = win_open::that("http://rust-lang.org").unwrap_err();
+//! assert_eq!(err.kind(), &ErrorKind::IO);
+//! mock::clear_runner();
+//! ```
+//!
+//! Record the programs that would be run.
+//!
+//! ```
+//! use std::{cell::RefCell, rc::Rc};
+//! use win_open::mock;
+//!
+//! let programs = Rc::new(RefCell::new(Vec::new()));
+//! let recorded = Rc::clone(&programs);
+//! mock::set_runner(move |cmd| {
+//!     recorded.borrow_mut().push(cmd.get_program().to_os_string());
+//!     Ok(mock::exit_status(0))
+//! });
+//! win_open::that("http://rust-lang.org").unwrap();
+//! mock::clear_runner();
+//!
+//! assert!(!programs.borrow().is_empty());
+//! ```
+
+use std::cell::RefCell;
+use std::io;
+use std::process::{Command, ExitStatus};
+
+type Runner = Box<dyn FnMut(&Command) -> io::Result<ExitStatus>>;
+
+thread_local! {
+    static RUNNER: RefCell<Option<Runner>> = const { RefCell::new(None) };
+}
+
+/// Sets the runner invoked in place of running commands on the current thread.
+///
+/// # Parameters
+/// - `runner`: A function receiving the fully built command and returning its simulated result.
+pub fn set_runner(runner: impl FnMut(&Command) -> io::Result<ExitStatus> + 'static) {
+    RUNNER.with(|cell| *cell.borrow_mut() = Some(Box::new(runner)));
+}
+
+/// Removes the runner of the current thread, so commands are run for real again.
+pub fn clear_runner() {
+    RUNNER.with(|cell| *cell.borrow_mut() = None);
+}
+
+/// Creates an `ExitStatus` with the given exit code, for use as a simulated result.
+///
+/// # Parameters
+/// - `code`: The exit code, where `0` means success.
+pub fn exit_status(code: u32) -> ExitStatus {
+    use std::os::windows::process::ExitStatusExt;
+    ExitStatus::from_raw(code)
+}
+
+/// Whether a runner is set on the current thread.
+pub(crate) fn is_active() -> bool {
+    RUNNER.with(|cell| cell.borrow().is_some())
+}
+
+/// Runs `cmd` through the runner of the current thread, if any.
+///
+/// # Returns
+/// `Some` result of the runner, or `None` if no runner is set.
+pub(crate) fn run(cmd: &Command) -> Option<io::Result<ExitStatus>> {
+    RUNNER.with(|cell| cell.borrow_mut().as_mut().map(|runner| runner(cmd)))
+}
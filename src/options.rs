@@ -0,0 +1,587 @@
+use crate::{AppArg, Error, ErrorKind, Result, StdioConfig, WindowsShell};
+use std::ffi::{OsStr, OsString};
+use std::process::Command;
+
+/// Options and flags which can be used to configure how a path is opened.
+///
+/// The free functions such as [`that()`](crate::that) and [`with()`](crate::with)
+/// use the default options. Create an `OpenOptions` with [`OpenOptions::new()`],
+/// chain calls to its setters and finally open a path with one of its methods.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// win_open::OpenOptions::new()
+///     .load_profile(true)
+///     .that("http://rust-lang.org")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct OpenOptions {
+    load_profile: bool,          // Whether PowerShell loads the user's profile
+    validate_app: bool,          // Whether the app is checked to be an executable
+    shell: Option<WindowsShell>, // The shell to use, or `None` to detect it
+    invoke_directly: bool,       // Whether PowerShell invokes the app directly
+    skip_log_usage: bool,        // Whether opened files are kept out of the recent files lists
+    create_process: bool,        // Whether absolute exe apps are launched with `CreateProcessW`
+    utf8_codepage: bool,         // Whether `cmd` switches to the UTF-8 codepage before launching
+    parent_window: isize,        // The window owning dialogs shown while opening, or 0 for none
+    expand_path: bool,           // Whether `%VAR%` and a leading `~` are expanded in paths
+    stdio: StdioConfig,          // The setup of the standard streams of launchers
+    kill_on_exit: bool,          // Whether detached launchers are terminated when the app exits
+    edit_fallback: bool,         // Whether files without an `open` verb are opened for editing
+    allow_device_paths: bool,    // Whether `\\.\` device paths may be opened
+}
+
+impl OpenOptions {
+    /// Creates a blank new set of options, equal to the defaults used by the free functions.
+    ///
+    /// # Returns
+    /// A new `OpenOptions` instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether PowerShell loads the user's profile before launching.
+    ///
+    /// By default `-NoProfile` is passed for performance. Enable this if the
+    /// launch relies on functions or aliases defined in the profile.
+    /// This option has no effect for other shells.
+    ///
+    /// # Parameters
+    /// - `load_profile`: `true` to load the profile, `false` to pass `-NoProfile`.
+    pub fn load_profile(&mut self, load_profile: bool) -> &mut Self {
+        self.load_profile = load_profile;
+        self
+    }
+
+    /// Sets whether PowerShell invokes the app directly (`& app path`), rather than through `Start-Process`.
+    ///
+    /// `Start-Process` detaches from the app and returns immediately. Invoking it directly
+    /// instead waits for the app to exit, so its exit code is reported.
+    /// This option only affects opening with an app, and has no effect for other shells.
+    ///
+    /// # Parameters
+    /// - `invoke_directly`: `true` to use the call operator, `false` to use `Start-Process`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use win_open::{OpenOptions, WindowsShell};
+    ///
+    /// let mut options = OpenOptions::new();
+    /// options.shell(WindowsShell::Powershell);
+    /// let started = options.with_command("notes.txt", "notepad");
+    /// let invoked = options.invoke_directly(true).with_command("notes.txt", "notepad");
+    ///
+    /// assert_eq!(started.get_args().nth(2).unwrap(), "Start-Process");
+    /// assert_eq!(invoked.get_args().nth(2).unwrap(), "&");
+    /// ```
+    pub fn invoke_directly(&mut self, invoke_directly: bool) -> &mut Self {
+        self.invoke_directly = invoke_directly;
+        self
+    }
+
+    /// Sets whether files opened with `ShellExecuteExW` are added to the recent files lists
+    /// of the app and the OS, as Explorer does.
+    ///
+    /// Enabled by default. This option only has an effect with the `shellexecute` feature,
+    /// for [`OpenOptions::that()`] and [`OpenOptions::that_detached()`].
+    ///
+    /// # Parameters
+    /// - `log_usage`: `true` to add opened files to the recent files lists.
+    pub fn log_usage(&mut self, log_usage: bool) -> &mut Self {
+        self.skip_log_usage = !log_usage;
+        self
+    }
+
+    /// Sets whether an app given as an absolute path to an `.exe` is launched directly with
+    /// `CreateProcessW`, skipping both the shell and `ShellExecuteExW`.
+    ///
+    /// This avoids spawning a shell, which is heavyweight in minimal environments.
+    /// Disabled by default. This option only has an effect with the `shellexecute` feature,
+    /// for [`OpenOptions::with()`]. Other apps are still launched through the shell.
+    ///
+    /// # Parameters
+    /// - `create_process`: `true` to launch absolute exe paths directly.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// win_open::OpenOptions::new()
+    ///     .create_process(true)
+    ///     .with("notes.txt", r"C:\Windows\System32\notepad.exe")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn create_process(&mut self, create_process: bool) -> &mut Self {
+        self.create_process = create_process;
+        self
+    }
+
+    /// Sets whether `cmd` switches the console codepage to UTF-8 (`chcp 65001`) before launching.
+    ///
+    /// On some codepage configurations, Unicode paths passed through `cmd` are mangled.
+    /// Enable this for reliable opening of Unicode named files regardless of the system codepage.
+    /// This option has no effect for other shells.
+    ///
+    /// # Parameters
+    /// - `utf8_codepage`: `true` to prefix `cmd` launches with `chcp 65001`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use win_open::{OpenOptions, WindowsShell};
+    ///
+    /// let cmds = OpenOptions::new()
+    ///     .shell(WindowsShell::Cmd)
+    ///     .utf8_codepage(true)
+    ///     .commands("résumé.pdf");
+    /// let args = cmds[0].get_args().collect::<Vec<_>>();
+    /// assert_eq!(args[..6], ["/c", "chcp", "65001", ">nul", "&&", "start"]);
+    /// ```
+    pub fn utf8_codepage(&mut self, utf8_codepage: bool) -> &mut Self {
+        self.utf8_codepage = utf8_codepage;
+        self
+    }
+
+    /// Sets whether environment variables (`%VAR%`) and a leading `~` are expanded in paths.
+    ///
+    /// Shells are inconsistent here: `cmd` expands `%VAR%` but not `~`, while PowerShell's
+    /// `Start-Process` expands neither. With this option, paths are expanded before launching,
+    /// so they behave the same with every shell. `~` is expanded to the home directory
+    /// (`USERPROFILE`), and undefined variables are kept as-is, like `cmd` does.
+    /// Disabled by default.
+    ///
+    /// # Parameters
+    /// - `expand_path`: `true` to expand paths.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use win_open::{OpenOptions, WindowsShell};
+    ///
+    /// let home = std::env::var("USERPROFILE").unwrap();
+    /// let mut options = OpenOptions::new();
+    /// options.shell(WindowsShell::Cmd).expand_path(true);
+    ///
+    /// let cmds = options.commands(r"%USERPROFILE%\Documents\file.txt");
+    /// let expected = format!(r#""{home}\Documents\file.txt""#);
+    /// assert_eq!(cmds[0].get_args().last().unwrap(), expected.as_str());
+    ///
+    /// let cmds = options.commands("~/file.txt");
+    /// let expected = format!(r#""{home}/file.txt""#);
+    /// assert_eq!(cmds[0].get_args().last().unwrap(), expected.as_str());
+    /// ```
+    pub fn expand_path(&mut self, expand_path: bool) -> &mut Self {
+        self.expand_path = expand_path;
+        self
+    }
+
+    /// Sets up the standard streams of the commands run by [`OpenOptions::that()`],
+    /// [`OpenOptions::with()`] and [`OpenBatch::flush()`](crate::OpenBatch::flush).
+    ///
+    /// By default all streams are nulled. This has no effect on paths opened with `ShellExecuteExW`.
+    ///
+    /// # Parameters
+    /// - `stdio`: The setup of the streams.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use win_open::{OpenOptions, StdioConfig, StdioMode};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// OpenOptions::new()
+    ///     .stdio(StdioConfig {
+    ///         stderr: StdioMode::Inherit,
+    ///         ..StdioConfig::default()
+    ///     })
+    ///     .that("notes.txt")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn stdio(&mut self, stdio: StdioConfig) -> &mut Self {
+        self.stdio = stdio;
+        self
+    }
+
+    /// Sets whether detached launchers are terminated when the current process exits, even if it crashes.
+    ///
+    /// The launchers spawned by [`OpenOptions::that_detached()`] and by
+    /// [`OpenBatch::flush()`](crate::OpenBatch::flush) are assigned to a job object
+    /// with `JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE` (see [`JobObject`](crate::JobObject)), which is
+    /// closed when the process exits. Note that this includes the apps they start, which join the job.
+    /// As only spawned processes can be assigned, paths are opened with a shell command
+    /// rather than `ShellExecuteExW`.
+    ///
+    /// Disabled by default. This option only has an effect with the `shellexecute` feature.
+    ///
+    /// # Parameters
+    /// - `kill_on_exit`: `true` to terminate launchers when the current process exits.
+    pub fn kill_on_exit(&mut self, kill_on_exit: bool) -> &mut Self {
+        self.kill_on_exit = kill_on_exit;
+        self
+    }
+
+    /// Sets whether paths in the Win32 device namespace, such as `\\.\PhysicalDrive0`, may be opened.
+    ///
+    /// Handing devices to the shell could have surprising or dangerous effects, so they're
+    /// rejected with an error of kind [`ErrorKind::INVALID_INPUT`] by default. This is a safety
+    /// guard for apps forwarding user input.
+    ///
+    /// # Parameters
+    /// - `allow`: `true` to open device paths like any other path.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use win_open::{ErrorKind, OpenOptions};
+    ///
+    /// let err = OpenOptions::new().that(r"\\.\PhysicalDrive0").unwrap_err();
+    /// assert_eq!(err.kind(), &ErrorKind::INVALID_INPUT);
+    /// ```
+    ///
+    /// The guard applies to the target a path resolves to, so other spellings of a device,
+    /// such as extended paths or `file://` URLs, are rejected too.
+    ///
+    /// ```
+    /// use win_open::{ErrorKind, OpenOptions};
+    ///
+    /// for path in [
+    ///     r"\\?\PhysicalDrive0",
+    ///     r"\\?\GLOBALROOT\Device\Harddisk0\Partition0",
+    ///     "file://./PhysicalDrive0",
+    ///     "file://%2E/PhysicalDrive0",
+    /// ] {
+    ///     let err = OpenOptions::new().that(path).unwrap_err();
+    ///     assert_eq!(err.kind(), &ErrorKind::INVALID_INPUT);
+    /// }
+    /// ```
+    ///
+    /// Device paths are rejected by default, but opened like any other path once allowed.
+    ///
+    /// ```
+    /// # #[cfg(feature = "mock")]
+    /// # {
+    /// use win_open::{mock, ErrorKind, OpenOptions, WindowsShell};
+    ///
+    /// mock::set_runner(|_| Ok(mock::exit_status(0)));
+    /// let mut options = OpenOptions::new();
+    /// options.shell(WindowsShell::Cmd);
+    /// let rejected = options.that(r"\\.\PhysicalDrive0");
+    /// let allowed = options.allow_device_paths(true).that(r"\\.\PhysicalDrive0");
+    /// mock::clear_runner();
+    ///
+    /// assert_eq!(rejected.unwrap_err().kind(), &ErrorKind::INVALID_INPUT);
+    /// assert!(allowed.is_ok());
+    /// # }
+    /// ```
+    pub fn allow_device_paths(&mut self, allow: bool) -> &mut Self {
+        self.allow_device_paths = allow;
+        self
+    }
+
+    /// Sets whether files without an association for the `open` verb are opened with `edit` instead.
+    ///
+    /// Some file types, such as PowerShell scripts (`.ps1`), have no `open` verb but do have
+    /// `edit`. With this option, when `ShellExecuteExW` reports no association for opening such
+    /// a file, the `edit` verb is tried, rather than failing with an error of kind
+    /// [`ErrorKind::NO_ASSOCIATION`]. If there's no `edit` verb either, its error is returned.
+    ///
+    /// Disabled by default. This option only has an effect with the `shellexecute` feature,
+    /// when paths are opened with `ShellExecuteExW`.
+    ///
+    /// # Parameters
+    /// - `edit_fallback`: `true` to try the `edit` verb for files that can't be opened.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// // Opens the script in an editor, rather than failing or running it.
+    /// win_open::OpenOptions::new()
+    ///     .edit_fallback(true)
+    ///     .that("deploy.ps1")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn edit_fallback(&mut self, edit_fallback: bool) -> &mut Self {
+        self.edit_fallback = edit_fallback;
+        self
+    }
+
+    /// Sets the window owning the dialogs shown while opening, such as "Open with",
+    /// UAC prompts or error messages, given as an `HWND`.
+    ///
+    /// The dialogs are then modal to, and centered on, this window, as expected by GUI apps.
+    /// By default they have no owner. This option only has an effect with the `shellexecute`
+    /// feature, when paths are opened with `ShellExecuteExW`.
+    ///
+    /// # Parameters
+    /// - `hwnd`: The handle of the owner window, or `0` for none.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let hwnd = 0;
+    /// win_open::OpenOptions::new()
+    ///     .parent_window(hwnd)
+    ///     .that("notes.txt")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn parent_window(&mut self, hwnd: isize) -> &mut Self {
+        self.parent_window = hwnd;
+        self
+    }
+
+    /// Sets the shell used to open paths, instead of detecting it.
+    ///
+    /// With an explicit shell, no shell detection is performed, so no probe processes are spawned.
+    /// This is useful in sandboxed environments where spawning processes may be blocked or logged.
+    ///
+    /// # Parameters
+    /// - `shell`: The shell to use.
+    ///
+    /// # Examples
+    ///
+    /// A shell chosen explicitly always runs a command, even with the `shellexecute-open` feature,
+    /// which otherwise opens with `ShellExecuteExW`. Without the feature, a command is always run.
+    ///
+    /// ```
+    /// # #[cfg(feature = "mock")]
+    /// # {
+    /// use std::{cell::Cell, rc::Rc};
+    /// use win_open::{mock, OpenOptions, WindowsShell};
+    ///
+    /// let runs = Rc::new(Cell::new(0));
+    /// let counted = Rc::clone(&runs);
+    /// mock::set_runner(move |_| {
+    ///     counted.set(counted.get() + 1);
+    ///     Ok(mock::exit_status(0))
+    /// });
+    /// OpenOptions::new().shell(WindowsShell::Cmd).that("notes.txt").unwrap();
+    /// OpenOptions::new().shell(WindowsShell::Cmd).that_detached("notes.txt").unwrap();
+    /// mock::clear_runner();
+    ///
+    /// assert_eq!(runs.get(), 2);
+    /// # }
+    /// ```
+    ///
+    /// This includes app URIs, which are handed to `explorer`, while `file:` URLs are opened
+    /// as the path they point to.
+    ///
+    /// ```
+    /// # #[cfg(feature = "mock")]
+    /// # {
+    /// use std::{cell::RefCell, rc::Rc};
+    /// use win_open::{mock, OpenOptions, WindowsShell};
+    ///
+    /// let programs = Rc::new(RefCell::new(Vec::new()));
+    /// let recorded = Rc::clone(&programs);
+    /// mock::set_runner(move |cmd| {
+    ///     recorded.borrow_mut().push(cmd.get_program().to_os_string());
+    ///     Ok(mock::exit_status(0))
+    /// });
+    /// let mut options = OpenOptions::new();
+    /// options.shell(WindowsShell::Cmd);
+    /// options.that("ms-settings:display").unwrap();
+    /// options.that("file:///C:/Windows/win.ini").unwrap();
+    /// mock::clear_runner();
+    ///
+    /// assert_eq!(*programs.borrow(), ["explorer.exe", "cmd.exe"]);
+    /// # }
+    /// ```
+    pub fn shell(&mut self, shell: WindowsShell) -> &mut Self {
+        self.shell = Some(shell);
+        self
+    }
+
+    /// Sets whether the app passed to [`OpenOptions::with()`] is checked to be an executable.
+    ///
+    /// If enabled, the app must be found on `PATH` or registered in `App Paths` (like `firefox`),
+    /// and any extension must be an executable one (such as `.exe`, `.com`, `.bat` or `.cmd`).
+    /// This catches the common mistake of passing a document (e.g. `notes.txt`) as the app,
+    /// which the shell would try to "run". Disabled by default.
+    ///
+    /// # Parameters
+    /// - `validate_app`: `true` to check the app before launching it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use win_open::{ErrorKind, OpenOptions};
+    ///
+    /// let err = OpenOptions::new()
+    ///     .validate_app(true)
+    ///     .with("https://example.com", "notes.txt")
+    ///     .unwrap_err();
+    /// assert_eq!(err.kind(), &ErrorKind::INVALID_INPUT);
+    /// ```
+    ///
+    /// Apps registered in `App Paths` are accepted even if they aren't on `PATH`.
+    ///
+    /// ```
+    /// # #[cfg(feature = "mock")]
+    /// # {
+    /// use std::{cell::RefCell, rc::Rc};
+    /// use win_open::{mock, OpenOptions, WindowsShell};
+    ///
+    /// let programs = Rc::new(RefCell::new(Vec::new()));
+    /// let recorded = Rc::clone(&programs);
+    /// // The `App Paths` query succeeds, as if the app were registered.
+    /// mock::set_runner(move |cmd| {
+    ///     recorded.borrow_mut().push(cmd.get_program().to_os_string());
+    ///     Ok(mock::exit_status(0))
+    /// });
+    /// let result = OpenOptions::new()
+    ///     .validate_app(true)
+    ///     .shell(WindowsShell::Cmd)
+    ///     .with("https://example.com", "registered-only-app");
+    /// mock::clear_runner();
+    ///
+    /// assert!(result.is_ok());
+    /// assert_eq!(programs.borrow()[0], "reg.exe");
+    /// # }
+    /// ```
+    pub fn validate_app(&mut self, validate_app: bool) -> &mut Self {
+        self.validate_app = validate_app;
+        self
+    }
+
+    /// Open path with the default application, using these options.
+    ///
+    /// See documentation of [`that()`](crate::that) for more details.
+    pub fn that(&self, path: impl AsRef<OsStr>) -> Result<()> {
+        crate::that_with_options(self.expand(path.as_ref()), self).map(|_| ())
+    }
+
+    /// Open path with the default application using a detached process, using these options.
+    ///
+    /// See documentation of [`that_detached()`](crate::that_detached) for more details.
+    pub fn that_detached(&self, path: impl AsRef<OsStr>) -> Result<()> {
+        crate::that_detached_with_options(self.expand(path.as_ref()), self)
+    }
+
+    /// Open path with the given application, using these options.
+    ///
+    /// See documentation of [`with()`](crate::with) for more details.
+    pub fn with(&self, path: impl AsRef<OsStr>, app: impl AppArg) -> Result<()> {
+        crate::with_with_options(self.expand(path.as_ref()), app, self).map(|_| ())
+    }
+
+    /// Get multiple commands that open `path` with the default application, using these options.
+    ///
+    /// See documentation of [`commands()`](crate::commands) for more details.
+    pub fn commands<T: AsRef<OsStr>>(&self, path: T) -> Vec<Command> {
+        crate::commands_with_options(self.expand(path.as_ref()), self)
+    }
+
+    /// Get a command that uses `app` to open `path`, using these options.
+    ///
+    /// See documentation of [`with_command()`](crate::with_command) for more details.
+    pub fn with_command<T: AsRef<OsStr>>(&self, path: T, app: impl AppArg) -> Command {
+        crate::with_command_with_options(self.expand(path.as_ref()), app, self)
+    }
+
+    /// Whether opened files are added to the recent files lists.
+    pub(crate) fn logs_usage(&self) -> bool {
+        !self.skip_log_usage
+    }
+
+    /// `path` with `%VAR%` and a leading `~` expanded, if enabled.
+    pub(crate) fn expand(&self, path: &OsStr) -> OsString {
+        if self.expand_path {
+            crate::target::expand(path)
+        } else {
+            path.to_os_string()
+        }
+    }
+
+    /// Checks that `path` isn't a device path, unless they're allowed.
+    pub(crate) fn check_device_path(&self, path: &OsStr) -> Result<()> {
+        if !self.allow_device_paths && crate::target::is_device_path(path) {
+            return Err(Error::new(
+                ErrorKind::INVALID_INPUT,
+                format!("'{}' is a device path", path.to_string_lossy()),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether files without an `open` verb are opened with the `edit` verb instead.
+    pub(crate) fn falls_back_to_edit(&self) -> bool {
+        self.edit_fallback
+    }
+
+    /// Whether detached launchers are terminated when the current process exits.
+    pub(crate) fn kills_on_exit(&self) -> bool {
+        self.kill_on_exit
+    }
+
+    /// The setup of the standard streams of launchers.
+    pub(crate) fn get_stdio(&self) -> StdioConfig {
+        self.stdio
+    }
+
+    /// The window owning dialogs shown while opening, or 0 for none.
+    pub(crate) fn parent_hwnd(&self) -> isize {
+        self.parent_window
+    }
+
+    /// Whether absolute exe apps are launched with `CreateProcessW`.
+    pub(crate) fn creates_process(&self) -> bool {
+        self.create_process
+    }
+
+    /// Whether PowerShell invokes the app directly.
+    pub(crate) fn invokes_directly(&self) -> bool {
+        self.invoke_directly
+    }
+
+    /// The explicitly set shell, or the detected one.
+    pub(crate) fn resolve_shell(&self) -> WindowsShell {
+        self.shell.unwrap_or_else(crate::detect_shell)
+    }
+
+    /// The shell set in these options, or chosen explicitly for the process, if any.
+    pub(crate) fn explicit_shell(&self) -> Option<WindowsShell> {
+        self.shell.or_else(crate::explicit_shell)
+    }
+
+    /// Checks that `app` is an executable the shell can find, if enabled.
+    pub(crate) fn check_app(&self, app: &OsStr) -> Result<()> {
+        if self.validate_app
+            && !(crate::app::has_executable_extension(app) && crate::app::is_available(app))
+        {
+            return Err(Error::new(
+                ErrorKind::INVALID_INPUT,
+                format!("'{}' is not an executable", app.to_string_lossy()),
+            ));
+        }
+        Ok(())
+    }
+
+    /// The arguments run by `cmd` before `start`, switching to the UTF-8 codepage if enabled.
+    pub(crate) fn codepage_args(&self) -> &'static [&'static str] {
+        if self.utf8_codepage {
+            &["chcp", "65001", ">nul", "&&"]
+        } else {
+            &[]
+        }
+    }
+
+    /// The arguments controlling whether PowerShell loads the user's profile.
+    pub(crate) fn profile_args(&self) -> &'static [&'static str] {
+        if self.load_profile {
+            &[]
+        } else {
+            &["-NoProfile"]
+        }
+    }
+}
@@ -0,0 +1,20 @@
+//! A convenience module re-exporting the commonly used functions and types.
+//!
+//! Bring everything into scope with a single glob import.
+//!
+//! ```no_run
+//! use win_open::prelude::*;
+//!
+//! # fn main() -> Result<()> {
+//! that("http://rust-lang.org")?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub use crate::{
+    commands, open_urls_as_tabs, that, that_as, that_detached, with, with_any, with_command,
+    with_detached, with_or_default, BrowserFamily, CommandExt, Error, ErrorKind, OpenBatch,
+    OpenOptions, OpenSpec, Result, StdioConfig, StdioMode, WindowStyle, WindowsShell,
+};
+#[cfg(feature = "threads")]
+pub use crate::{that_in_background, with_in_background};
@@ -1,9 +1,20 @@
 use crate::error::{Error, ErrorKind, Result};
+use crate::{probe_timeout, CommandRunExt};
 use std::fmt::Debug;
+use std::process::Command;
 use std::str::FromStr;
 
 /// Enum representing the different types of Windows shells that can be used.
-#[derive(Debug, Copy, Clone)]
+///
+/// # Examples
+///
+/// ```
+/// use win_open::WindowsShell;
+///
+/// assert_eq!("pwsh".parse::<WindowsShell>(), Ok(WindowsShell::Powershell));
+/// assert_ne!(WindowsShell::Cmd, WindowsShell::Nushell);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum WindowsShell {
     /// PowerShell (`pwsh`).
     Powershell,
@@ -29,6 +40,127 @@ impl WindowsShell {
             WindowsShell::Cmd => "cmd",         // Command Prompt command
         }
     }
+
+    /// Returns the executable of the shell, including the `.exe` suffix.
+    ///
+    /// The explicit suffix is required when running under WSL interop,
+    /// where the bare names (e.g. "cmd") can't be resolved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use win_open::WindowsShell;
+    ///
+    /// assert_eq!(WindowsShell::Cmd.program(), "cmd.exe");
+    /// assert_eq!(WindowsShell::Powershell.program(), "pwsh.exe");
+    /// ```
+    ///
+    /// # Returns
+    /// A string slice representing the shell executable (e.g., "pwsh.exe", "nu.exe", "cmd.exe").
+    pub fn program(self) -> &'static str {
+        match self {
+            WindowsShell::Powershell => "pwsh.exe",
+            WindowsShell::Nushell => "nu.exe",
+            WindowsShell::Cmd => "cmd.exe",
+        }
+    }
+
+    /// Checks whether this shell is available, by running a harmless probe command with it.
+    ///
+    /// See [`probe()`](Self::probe) for details, and to find out why a shell isn't available.
+    ///
+    /// # Returns
+    /// `true` if a probe ran successfully, `false` otherwise.
+    pub fn is_available(self) -> bool {
+        self.probe().is_ok()
+    }
+
+    /// Runs a harmless probe command with this shell, to check whether it works.
+    ///
+    /// The bare name of the shell is probed first, followed by its `.exe` suffixed [`program()`](Self::program)
+    /// for WSL interop. A probe is killed if it doesn't finish within the timeout set by
+    /// [`set_probe_timeout()`](crate::set_probe_timeout), in which case it's considered to have failed.
+    ///
+    /// This distinguishes a shell that isn't installed from one that is, but doesn't work,
+    /// e.g. because it crashes on launch.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// match win_open::WindowsShell::Powershell.probe() {
+    ///     Ok(()) => println!("pwsh works"),
+    ///     Err(err) if err.is_shell_not_found() => println!("pwsh is not installed"),
+    ///     Err(err) => println!("pwsh is installed, but broken: {}", err),
+    /// }
+    /// ```
+    ///
+    /// Distinguish a shell that isn't installed from one whose probe fails.
+    ///
+    /// ```
+    /// # #[cfg(feature = "mock")]
+    /// # {
+    /// use std::io;
+    /// use win_open::{mock, ErrorKind, WindowsShell};
+    ///
+    /// mock::set_runner(|_| Err(io::Error::from(io::ErrorKind::NotFound)));
+    /// let err = WindowsShell::Powershell.probe().unwrap_err();
+    /// assert_eq!(err.kind(), &ErrorKind::SHELL_NOT_FOUND);
+    ///
+    /// mock::set_runner(|_| Ok(mock::exit_status(0xC0000005)));
+    /// let err = WindowsShell::Powershell.probe().unwrap_err();
+    /// assert_eq!(err.kind(), &ErrorKind::PROBE_FAILED);
+    /// mock::clear_runner();
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// A [`Error`] of kind [`ErrorKind::SHELL_NOT_FOUND`] is returned if the shell couldn't be found,
+    /// or of kind [`ErrorKind::PROBE_FAILED`] if it was found, but the probe failed or timed out.
+    pub fn probe(self) -> Result<()> {
+        let mut last_err = Error::new(ErrorKind::SHELL_NOT_FOUND, self.as_str());
+        for program in [self.as_str(), self.program()] {
+            let mut cmd = Command::new(program);
+            match self {
+                WindowsShell::Powershell => cmd.arg("-Command").arg("$PSVersionTable.PSVersion"),
+                WindowsShell::Nushell => cmd.arg("-c").arg("version"),
+                WindowsShell::Cmd => cmd.arg("/c").arg("ver"),
+            };
+
+            let reason = match cmd.status_with_timeout(probe_timeout()) {
+                Ok(Some(status)) if status.success() => return Ok(()),
+                Ok(Some(status)) => status.to_string(),
+                Ok(None) => "timed out".to_string(),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(err) => err.to_string(),
+            };
+            last_err = Error::new(ErrorKind::PROBE_FAILED, format!("{program}: {reason}"));
+        }
+        Err(last_err)
+    }
+
+    /// Checks the availability of every shell, e.g. for a diagnostics report.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// for (shell, available) in win_open::WindowsShell::probe_all() {
+    ///     println!("{}: {}", shell.as_str(), if available { "available" } else { "missing" });
+    /// }
+    /// ```
+    ///
+    /// # Returns
+    /// Each shell paired with whether it is available, in order of detection preference.
+    pub fn probe_all() -> Vec<(WindowsShell, bool)> {
+        [
+            WindowsShell::Powershell,
+            WindowsShell::Nushell,
+            WindowsShell::Cmd,
+        ]
+        .into_iter()
+        .map(|shell| (shell, shell.is_available()))
+        .collect()
+    }
 }
 
 impl TryInto<WindowsShell> for &str {
@@ -0,0 +1,83 @@
+use std::ffi::OsStr;
+use std::path::PathBuf;
+
+use crate::com::{check, ComPtr};
+use crate::{ffi, from_wide, wide, Error, ErrorKind, Result};
+
+/// The maximum length of a path, in characters, including the null character.
+const MAX_PATH: usize = 260;
+
+/// Resolves the file system target of the shell link (`.lnk`) at `path`, using `IShellLinkW`.
+///
+/// The link isn't resolved further, so a target that was moved or deleted is still returned.
+pub(crate) fn resolve(path: &OsStr) -> Result<PathBuf> {
+    let path = wide(path);
+    unsafe {
+        ffi::CoInitialize(std::ptr::null());
+
+        let mut link = std::ptr::null_mut();
+        check(ffi::CoCreateInstance(
+            &ffi::CLSID_SHELL_LINK,
+            std::ptr::null_mut(),
+            ffi::CLSCTX_INPROC_SERVER,
+            &ffi::IID_ISHELL_LINK_W,
+            &mut link,
+        ))?;
+        let link = ComPtr::<ffi::IShellLinkWVtbl>(link.cast());
+
+        let mut file = std::ptr::null_mut();
+        check((link.vtable().base.QueryInterface)(
+            link.this(),
+            &ffi::IID_IPERSIST_FILE,
+            &mut file,
+        ))?;
+        let file = ComPtr::<ffi::IPersistFileVtbl>(file.cast());
+        check((file.vtable().Load)(
+            file.this(),
+            path.as_ptr(),
+            ffi::STGM_READ,
+        ))?;
+
+        let mut target = vec![0u16; MAX_PATH];
+        check((link.vtable().GetPath)(
+            link.this(),
+            target.as_mut_ptr(),
+            target.len() as i32,
+            std::ptr::null_mut(),
+            0,
+        ))?;
+
+        // Links to non file system items, such as URLs or Control Panel items, have no path.
+        let target = from_wide(&target);
+        if target.is_empty() {
+            return Err(Error::new(
+                ErrorKind::NOT_FOUND,
+                format!(
+                    "'{}' has no file system target",
+                    from_wide(&path).to_string_lossy()
+                ),
+            ));
+        }
+        Ok(PathBuf::from(target))
+    }
+}
+
+/// Reads the URL of the internet shortcut (`.url`) at `path`.
+///
+/// Internet shortcuts are INI files, with the URL in the `URL` key of the `InternetShortcut` section.
+pub(crate) fn internet_shortcut_url(path: &OsStr) -> Result<String> {
+    let contents = std::fs::read_to_string(path)?;
+    let mut in_section = false;
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_section = line.eq_ignore_ascii_case("[InternetShortcut]");
+        } else if let Some(url) = line.strip_prefix("URL=").filter(|_| in_section) {
+            return Ok(url.to_string());
+        }
+    }
+
+    Err(Error::new(
+        ErrorKind::NOT_FOUND,
+        format!("'{}' has no URL", path.to_string_lossy()),
+    ))
+}
@@ -0,0 +1,240 @@
+use crate::{target, OpenOptions, Result, WindowsShell};
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+/// Enum representing how the window of the opened application is shown.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum WindowStyle {
+    /// Shown at its default size and position.
+    Normal,
+
+    /// Shown minimized.
+    Minimized,
+
+    /// Shown maximized.
+    Maximized,
+
+    /// Not shown. Only supported by PowerShell and `ShellExecuteExW`.
+    Hidden,
+}
+
+impl WindowStyle {
+    /// Returns the name of the style as accepted by PowerShell's `Start-Process -WindowStyle`.
+    ///
+    /// # Returns
+    /// A string slice representing the style (e.g., "Normal", "Minimized").
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WindowStyle::Normal => "Normal",
+            WindowStyle::Minimized => "Minimized",
+            WindowStyle::Maximized => "Maximized",
+            WindowStyle::Hidden => "Hidden",
+        }
+    }
+
+    /// Returns the `nShow` value passed to `ShellExecuteExW` for this style.
+    pub(crate) fn show_command(self) -> i32 {
+        match self {
+            WindowStyle::Normal => 1,    // SW_SHOWNORMAL
+            WindowStyle::Minimized => 2, // SW_SHOWMINIMIZED
+            WindowStyle::Maximized => 3, // SW_SHOWMAXIMIZED
+            WindowStyle::Hidden => 0,    // SW_HIDE
+        }
+    }
+}
+
+/// A specification of how to open a target, bundling the shell, verb, app, arguments,
+/// window style and working directory.
+///
+/// This is the most general way to open a path, used with [`open_with()`](crate::open_with)
+/// and [`open_with_command()`](crate::open_with_command). Everything is optional; an empty
+/// `OpenSpec` opens the target with the default application, like [`that()`](crate::that).
+///
+/// # Examples
+///
+/// ```no_run
+/// use win_open::{OpenSpec, WindowStyle};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut spec = OpenSpec::new();
+/// spec.app("notepad").show(WindowStyle::Maximized).dir(r"C:\Users\Public");
+/// win_open::open_with("notes.txt", &spec)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct OpenSpec {
+    shell: Option<WindowsShell>, // The shell to use, or `None` to detect it
+    verb: Option<String>,        // The verb, such as `runas`, or `None` for the default verb
+    app: Option<String>,         // The app to open the target with, or `None` for the default app
+    args: Vec<OsString>,         // Additional arguments passed after the target
+    show: Option<WindowStyle>,   // How the window is shown, or `None` for the default
+    dir: Option<PathBuf>,        // The working directory, or `None` to inherit it
+    parent_window: isize,        // The window owning dialogs shown while opening, or 0 for none
+    options: OpenOptions,        // The options used to check the target and build the command
+}
+
+impl OpenSpec {
+    /// Creates an empty spec, opening a target with the default application.
+    ///
+    /// # Returns
+    /// A new `OpenSpec` instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the shell used to open the target, instead of detecting it.
+    ///
+    /// # Parameters
+    /// - `shell`: The shell to use.
+    pub fn shell(&mut self, shell: WindowsShell) -> &mut Self {
+        self.shell = Some(shell);
+        self
+    }
+
+    /// Sets the verb used to open the target, such as `runas`, `edit` or `print`.
+    ///
+    /// Verbs are only supported by PowerShell and `ShellExecuteExW`.
+    ///
+    /// # Parameters
+    /// - `verb`: The verb to use.
+    pub fn verb(&mut self, verb: impl Into<String>) -> &mut Self {
+        self.verb = Some(verb.into());
+        self
+    }
+
+    /// Sets the app used to open the target, instead of the default application.
+    ///
+    /// # Parameters
+    /// - `app`: The app to use.
+    pub fn app(&mut self, app: impl Into<String>) -> &mut Self {
+        self.app = Some(app.into());
+        self
+    }
+
+    /// Adds an argument, passed after the target.
+    ///
+    /// # Parameters
+    /// - `arg`: The argument to add.
+    pub fn arg(&mut self, arg: impl AsRef<OsStr>) -> &mut Self {
+        self.args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    /// Adds multiple arguments, passed after the target.
+    ///
+    /// # Parameters
+    /// - `args`: The arguments to add.
+    pub fn args<I, T>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<OsStr>,
+    {
+        for arg in args {
+            self.arg(arg);
+        }
+        self
+    }
+
+    /// Sets how the window of the opened application is shown.
+    ///
+    /// Nushell doesn't support window styles, and `cmd` doesn't support `WindowStyle::Hidden`,
+    /// so opening with them fails rather than ignoring the style.
+    ///
+    /// # Parameters
+    /// - `show`: The window style.
+    pub fn show(&mut self, show: WindowStyle) -> &mut Self {
+        self.show = Some(show);
+        self
+    }
+
+    /// Sets the working directory of the opened application.
+    ///
+    /// # Parameters
+    /// - `dir`: The working directory.
+    pub fn dir(&mut self, dir: impl Into<PathBuf>) -> &mut Self {
+        self.dir = Some(dir.into());
+        self
+    }
+
+    /// Sets the window owning the dialogs shown while opening, such as UAC prompts,
+    /// given as an `HWND`.
+    ///
+    /// This is only supported by `ShellExecuteExW`.
+    /// See [`OpenOptions::parent_window()`](crate::OpenOptions::parent_window).
+    ///
+    /// # Parameters
+    /// - `hwnd`: The handle of the owner window, or `0` for none.
+    pub fn parent_window(&mut self, hwnd: isize) -> &mut Self {
+        self.parent_window = hwnd;
+        self
+    }
+
+    /// Sets the options used to open the target, such as whether PowerShell loads the profile
+    /// or `cmd` switches to the UTF-8 codepage. Their checks, such as the allowed device paths,
+    /// apply to the target too. A shell set in `options` is used unless one is set in the spec.
+    ///
+    /// # Parameters
+    /// - `options`: The options to use.
+    pub fn options(&mut self, options: OpenOptions) -> &mut Self {
+        self.options = options;
+        self
+    }
+
+    /// The explicitly set shell, or the detected one.
+    pub(crate) fn resolve_shell(&self) -> WindowsShell {
+        self.shell.unwrap_or_else(|| self.options.resolve_shell())
+    }
+
+    /// Whether the shell was set explicitly.
+    pub(crate) fn has_shell(&self) -> bool {
+        self.shell.is_some()
+    }
+
+    /// The verb, if any.
+    pub(crate) fn get_verb(&self) -> Option<&str> {
+        self.verb.as_deref()
+    }
+
+    /// The app, if any.
+    pub(crate) fn get_app(&self) -> Option<&str> {
+        self.app.as_deref()
+    }
+
+    /// The additional arguments.
+    pub(crate) fn get_args(&self) -> &[OsString] {
+        &self.args
+    }
+
+    /// The window style, if any.
+    pub(crate) fn get_show(&self) -> Option<WindowStyle> {
+        self.show
+    }
+
+    /// The working directory, if any.
+    pub(crate) fn get_dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// The options used to open the target.
+    pub(crate) fn get_options(&self) -> &OpenOptions {
+        &self.options
+    }
+
+    /// The window owning dialogs, or 0 for none.
+    pub(crate) fn get_parent_window(&self) -> isize {
+        self.parent_window
+    }
+
+    /// Fails if the verb, the app, an argument or the working directory contains a NUL character.
+    pub(crate) fn check_nul(&self) -> Result<()> {
+        let verb = self.verb.as_deref().map(OsStr::new);
+        let app = self.app.as_deref().map(OsStr::new);
+        let dir = self.dir.as_deref().map(Path::as_os_str);
+        [verb, app, dir]
+            .into_iter()
+            .flatten()
+            .chain(self.args.iter().map(OsString::as_os_str))
+            .try_for_each(target::check_nul)
+    }
+}
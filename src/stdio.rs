@@ -0,0 +1,69 @@
+use std::process::{Command, Stdio};
+
+/// Enum representing how a standard stream of a launched command is set up.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum StdioMode {
+    /// The stream is connected to the null device.
+    #[default]
+    Null,
+
+    /// The stream is inherited from the current process, e.g. to see the errors of a launcher.
+    Inherit,
+}
+
+impl StdioMode {
+    /// Creates the `Stdio` for this mode.
+    fn to_stdio(self) -> Stdio {
+        match self {
+            StdioMode::Null => Stdio::null(),
+            StdioMode::Inherit => Stdio::inherit(),
+        }
+    }
+}
+
+/// The setup of the standard streams of the commands run to open a path.
+///
+/// By default all streams are nulled, so launchers don't write to the console of your app.
+/// Set it with [`OpenOptions::stdio()`](crate::OpenOptions::stdio), e.g. to see the errors of
+/// a launcher while debugging.
+///
+/// # Examples
+///
+/// ```
+/// use std::process::Command;
+/// use win_open::{StdioConfig, StdioMode};
+///
+/// let stdio = StdioConfig {
+///     stderr: StdioMode::Inherit,
+///     ..StdioConfig::default()
+/// };
+/// assert_eq!(stdio.stdin, StdioMode::Null);
+/// assert_eq!(stdio.stdout, StdioMode::Null);
+/// assert_eq!(stdio.stderr, StdioMode::Inherit);
+///
+/// let mut cmd = Command::new("cmd.exe");
+/// stdio.apply(&mut cmd);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct StdioConfig {
+    /// The setup of the standard input.
+    pub stdin: StdioMode,
+
+    /// The setup of the standard output.
+    pub stdout: StdioMode,
+
+    /// The setup of the standard error.
+    pub stderr: StdioMode,
+}
+
+impl StdioConfig {
+    /// Sets up the standard streams of `cmd` as configured.
+    ///
+    /// # Parameters
+    /// - `cmd`: The command to set up.
+    pub fn apply(self, cmd: &mut Command) -> &mut Command {
+        cmd.stdin(self.stdin.to_stdio())
+            .stdout(self.stdout.to_stdio())
+            .stderr(self.stderr.to_stdio())
+    }
+}
@@ -0,0 +1,371 @@
+use std::borrow::Cow;
+use std::ffi::{OsStr, OsString};
+use std::path::{Component, Path, PathBuf};
+
+use crate::{Error, ErrorKind, Result};
+
+/// Returns the URL scheme of `path` (e.g. `https` or `ms-settings`), if any.
+///
+/// Single letter schemes are treated as drive letters (e.g. `C:\`), not URLs.
+pub(crate) fn scheme(path: &OsStr) -> Option<String> {
+    let path = path.to_string_lossy();
+    let (scheme, _) = path.split_once(':')?;
+    let valid = scheme.len() > 1
+        && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+
+    valid.then(|| scheme.to_ascii_lowercase())
+}
+
+/// Returns `true` if `path` starts with a URL scheme such as `https:`.
+pub(crate) fn is_url(path: &OsStr) -> bool {
+    scheme(path).is_some()
+}
+
+/// Returns `true` if `path` is a protocol URI handled by an app rather than a browser,
+/// such as `ms-settings:display` or `ms-availablenetworks:`.
+///
+/// This includes shell folders such as `shell:RecycleBinFolder`, but not `file:` URLs,
+/// which are opened as the path they point to.
+pub(crate) fn is_app_uri(path: &OsStr) -> bool {
+    scheme(path).is_some_and(|scheme| !matches!(scheme.as_str(), "http" | "https" | "file"))
+}
+
+/// Returns `true` if `path` is a Control Panel applet file, such as `ncpa.cpl`.
+pub(crate) fn is_control_panel_applet(path: &OsStr) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("cpl"))
+}
+
+/// Returns the argument for `control.exe` to open `path` with, if it's a Control Panel item.
+///
+/// This is either a `control:` URI such as `control:printers`, whose canonical name is returned,
+/// or a Control Panel applet file such as `ncpa.cpl`, which is returned as-is.
+pub(crate) fn control_panel_item(path: &OsStr) -> Option<OsString> {
+    if scheme(path).as_deref() == Some("control") {
+        let path = path.to_string_lossy();
+        return Some(path["control:".len()..].into());
+    }
+
+    is_control_panel_applet(path).then(|| path.to_os_string())
+}
+
+/// Checks that `value` contains no NUL characters.
+///
+/// Win32 strings are NUL terminated, so an interior NUL would silently truncate the value.
+pub(crate) fn check_nul(value: &OsStr) -> Result<()> {
+    use std::os::windows::ffi::OsStrExt;
+
+    if value.encode_wide().any(|c| c == 0) {
+        return Err(Error::new(
+            ErrorKind::INVALID_INPUT,
+            format!("'{}' contains a NUL character", value.to_string_lossy()),
+        ));
+    }
+    Ok(())
+}
+
+/// Returns `true` if `path` is in the Win32 device namespace, such as `\\.\PhysicalDrive0`.
+///
+/// Extended (`\\?\`) and NT (`\??\`) paths reach devices too, e.g. `\\?\PhysicalDrive0`
+/// or `\\?\GLOBALROOT\Device\Harddisk0\Partition0`, unless they name a file or folder
+/// on a drive (`\\?\C:\`), a share (`\\?\UNC\`) or a volume (`\\?\Volume{...}\`).
+pub(crate) fn is_device_path(path: &OsStr) -> bool {
+    let path = path.to_string_lossy().replace('/', "\\");
+    if path.starts_with(r"\\.\") {
+        return true;
+    }
+    let Some(rest) = path
+        .strip_prefix(r"\\?\")
+        .or_else(|| path.strip_prefix(r"\??\"))
+    else {
+        return false;
+    };
+
+    let bytes = rest.as_bytes();
+    let is_drive = bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && &bytes[1..3] == b":\\";
+    let is_share = rest
+        .get(..4)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case(r"UNC\"));
+    let is_volume = rest
+        .get(..7)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case("Volume{"))
+        && rest.contains("}\\");
+    !(is_drive || is_share || is_volume)
+}
+
+/// Strips the whitespace and a single layer of quotes surrounding a pasted `path`.
+///
+/// Paths copied from Explorer are wrapped in quotes, e.g. `"C:\notes.txt"`, which would
+/// otherwise be quoted twice. Quotes can't be part of Windows filenames, so they're only kept
+/// if `path` contains more of them. Whitespace can end a filename, so an existing `path`
+/// is kept as-is. Stripping is idempotent, so `path` may be stripped more than once.
+pub(crate) fn strip_surrounding(path: &OsStr) -> Cow<'_, OsStr> {
+    use std::os::windows::ffi::{OsStrExt, OsStringExt};
+
+    const QUOTE: u16 = b'"' as u16;
+
+    let wide: Vec<u16> = path.encode_wide().collect();
+    let is_space = |c: &u16| char::from_u32(u32::from(*c)).is_some_and(char::is_whitespace);
+    let mut range = 0..wide.len();
+    if !exists(path) {
+        range.start = wide.iter().position(|c| !is_space(c)).unwrap_or(wide.len());
+        range.end = wide
+            .iter()
+            .rposition(|c| !is_space(c))
+            .map_or(range.start, |end| end + 1);
+    }
+    // Whitespace inside the quotes is kept, as it may end the filename.
+    if let [QUOTE, inner @ .., QUOTE] = &wide[range.clone()] {
+        if !inner.contains(&QUOTE) {
+            range = range.start + 1..range.end - 1;
+        }
+    }
+
+    if range == (0..wide.len()) {
+        Cow::Borrowed(path)
+    } else {
+        Cow::Owned(OsString::from_wide(&wide[range]))
+    }
+}
+
+/// Returns `true` if `path` exists, including files whose name ends with a dot or a space.
+fn exists(path: &OsStr) -> bool {
+    // Path normalization trims trailing dots and spaces, which the extended form keeps.
+    Path::new(path).exists() || extended_path(path).is_some_and(|path| Path::new(&path).exists())
+}
+
+/// Expands environment variables (`%VAR%`) and a leading `~` (the home directory) in `path`.
+///
+/// Like `cmd`, undefined variables and unpaired `%` are kept as-is.
+/// `~` is only expanded on its own or followed by a separator, e.g. `~\file.txt` but not `~user`.
+pub(crate) fn expand(path: &OsStr) -> OsString {
+    let lossy = path.to_string_lossy();
+    if !lossy.starts_with('~') && !lossy.contains('%') {
+        // Keep paths that need no expansion exactly as given, without a lossy conversion.
+        return path.to_os_string();
+    }
+    let path = lossy;
+    let mut expanded = String::with_capacity(path.len());
+
+    let mut rest = &*path;
+    if let Some(after) = rest.strip_prefix('~') {
+        if after.is_empty() || after.starts_with(['\\', '/']) {
+            if let Some(home) = std::env::var_os("USERPROFILE") {
+                expanded.push_str(&home.to_string_lossy());
+                rest = after;
+            }
+        }
+    }
+
+    while let Some(start) = rest.find('%') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('%') {
+            Some(end) => match std::env::var_os(&after[..end]).filter(|_| end > 0) {
+                Some(value) => {
+                    expanded.push_str(&value.to_string_lossy());
+                    rest = &after[end + 1..];
+                }
+                None => {
+                    // Keep the `%` literally, the closing one may start a defined variable.
+                    expanded.push('%');
+                    rest = after;
+                }
+            },
+            None => {
+                expanded.push('%');
+                rest = after;
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded.into()
+}
+
+/// Resolves the target to open for `path`.
+///
+/// `file://` URLs are converted to paths, other URLs are returned unchanged
+/// and local paths are made absolute.
+pub(crate) fn resolve(path: &OsStr) -> Result<OsString> {
+    check_nul(path)?;
+    let path = &*strip_surrounding(path);
+
+    if let Some(path) = file_url_to_path(path) {
+        return resolve(path.as_os_str());
+    }
+
+    if is_url(path) {
+        return Ok(path.to_os_string());
+    }
+
+    // Bare applet names such as `ncpa.cpl` are found by `control.exe` in the system directory.
+    if is_control_panel_applet(path) && Path::new(path).components().count() == 1 {
+        return Ok(path.to_os_string());
+    }
+
+    #[cfg(not(feature = "shellexecute-open"))]
+    {
+        Ok(std::path::absolute(path)?.into_os_string())
+    }
+
+    #[cfg(feature = "shellexecute-open")]
+    {
+        if path.is_empty() {
+            return Err(std::io::Error::from(std::io::ErrorKind::InvalidInput).into());
+        }
+        Ok(extended_path(path).unwrap_or_else(|| crate::normalize_path(path)))
+    }
+}
+
+/// Returns `true` if `path` is a local path with a component ending with a dot or a space,
+/// e.g. `file.txt.` or `folder `.
+///
+/// Such names are legal on disk, but are trimmed by the shell and by path normalization.
+/// URLs, which may well end with a dot, are never affected.
+pub(crate) fn has_trailing_dots_or_spaces(path: &OsStr) -> bool {
+    scheme(path).is_none()
+        && Path::new(path)
+            .components()
+            .any(|component| match component {
+                Component::Normal(name) => name.to_string_lossy().ends_with(['.', ' ']),
+                _ => false,
+            })
+}
+
+/// Converts `path` to the extended `\\?\` form, which preserves trailing dots and spaces.
+///
+/// # Returns
+/// The extended path, or `None` if `path` has no trailing dots or spaces, or is already extended.
+pub(crate) fn extended_path(path: &OsStr) -> Option<OsString> {
+    if !has_trailing_dots_or_spaces(path) || path.to_string_lossy().starts_with(r"\\?\") {
+        return None;
+    }
+
+    let path = Path::new(path);
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().ok()?.join(path)
+    };
+    // Extended paths are passed to the file system as-is, so `..` and separators aren't resolved.
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    let path = normalized.to_string_lossy().replace('/', "\\");
+
+    Some(match path.strip_prefix(r"\\") {
+        Some(unc) => format!(r"\\?\UNC\{unc}").into(),
+        None => format!(r"\\?\{path}").into(),
+    })
+}
+
+/// Converts a `file://` URL back to a Windows path.
+///
+/// Handles local paths (`file:///C:/dir/file.txt`), UNC paths (`file://server/share/file.txt`)
+/// and percent-encoded characters such as spaces (`%20`).
+///
+/// # Returns
+/// The path, or `None` if `url` isn't a `file://` URL.
+pub(crate) fn file_url_to_path(url: &OsStr) -> Option<PathBuf> {
+    if scheme(url).as_deref() != Some("file") {
+        return None;
+    }
+
+    const LOCALHOST: &str = "localhost/";
+
+    let url = url.to_string_lossy();
+    let rest = &url["file:".len()..];
+    let path = match rest.strip_prefix("//") {
+        // `file:///C:/...`
+        Some(rest) if rest.starts_with('/') => rest.trim_start_matches('/').to_string(),
+        // `file://localhost/C:/...`
+        Some(rest)
+            if rest
+                .get(..LOCALHOST.len())
+                .is_some_and(|host| host.eq_ignore_ascii_case(LOCALHOST)) =>
+        {
+            rest[LOCALHOST.len()..].to_string()
+        }
+        // `file://server/share/...`
+        Some(rest) => format!("//{rest}"),
+        // `file:/C:/...` or `file:C:/...`
+        None => rest.trim_start_matches('/').to_string(),
+    };
+
+    Some(PathBuf::from(percent_decode(&path).replace('/', "\\")))
+}
+
+/// Builds a `mailto:` URI composing an email to `to`, with the given `subject` and `body`.
+///
+/// Recipients are separated by commas, and every value is percent-encoded. Line breaks
+/// in `body` are normalized to `%0D%0A`, as required by RFC 6068. Empty fields are omitted.
+pub(crate) fn mailto_uri(to: &[&str], subject: &str, body: &str) -> String {
+    let recipients: Vec<_> = to.iter().map(|to| percent_encode(to, "@")).collect();
+    let mut uri = format!("mailto:{}", recipients.join(","));
+
+    let body = body
+        .replace("\r\n", "\n")
+        .replace('\r', "\n")
+        .replace('\n', "\r\n");
+    let fields = [("subject", subject), ("body", body.as_str())];
+    let mut separator = '?';
+    for (name, value) in fields.iter().filter(|(_, value)| !value.is_empty()) {
+        uri.push(separator);
+        uri.push_str(name);
+        uri.push('=');
+        uri.push_str(&percent_encode(value, ""));
+        separator = '&';
+    }
+    uri
+}
+
+/// Percent-encodes every byte of `input` except unreserved characters and those in `keep`.
+fn percent_encode(input: &str, keep: &str) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        if byte.is_ascii_alphanumeric()
+            || b"-._~".contains(&byte)
+            || keep.as_bytes().contains(&byte)
+        {
+            encoded.push(char::from(byte));
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+/// Decodes percent-encoded characters, leaving invalid sequences as-is.
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}